@@ -5,7 +5,9 @@
 //! It is dereferencable as `&T`.
 //!
 //! And if you use macro [cause], it automatically stores some extra information,
-//! the filename and line number, only when it was compiled with `debug_assertions`.
+//! the filename, line and column number, only when it was compiled with `debug_assertions`
+//! or with `release-location` feature, and never with `no-location` feature.
+//! They are available through `Cause::location()`.
 //!
 //! # Examples
 //!
@@ -40,7 +42,7 @@
 //! // set the message:
 //! let cause = Cause::new(InvalidArgumentsError).msg("oops!");
 //! assert_eq!(cause.to_string(), "InvalidArgumentsError: oops!".to_string());
-//! assert_eq!(cause.message(), Some(&"oops!".to_string()));
+//! assert_eq!(cause.message(), Some("oops!"));
 //! assert!(cause.source().is_none());
 //!
 //! // set the source of this error (any error type can be set with `src()`):
@@ -64,126 +66,3120 @@
 //! let cause = cause!(InternalError);
 //! println!("{}", cause);
 //!   // => "InternalError" on release build
-//!   // => "InternalError: [lib.rs:64]" on debug build
+//!   // => "InternalError: [lib.rs:64:13]" on debug build
 //!
 //! let cause = cause!(NotFoundError, "There is no such contents.");
 //! println!("{}", cause);
 //!   // => "InternalError: There is no such contents." on release build
-//!   // => "InternalError: There is no such contents. [lib.rs:69]" on debug build
+//!   // => "InternalError: There is no such contents. [lib.rs:69:13]" on debug build
+//!
+//! let cause = cause!(InternalError, "Failed to read the contents."; IoErr::new(ErrorKind::Other, "oh no!"));
+//! println!("{}", cause);
+//!   // => "InternalError: Failed to read the contents.\n\nCaused by:\n    oh no!\n" on release build
+//!   // => "InternalError: Failed to read the contents. [lib.rs:74:13]\n\nCaused by:\n    oh no!\n" on debug build
 //!
 //! ```
+//!
+//! # Features
+//!
+//! - `release-location`: records the locations even when compiled without `debug_assertions`.
+//! - `no-location`: never records the locations, so that the file names are not embedded in the binary.
+//!   It takes precedence over `release-location`. [Cause::with_location] still works.
+//! - `on-create`: enables `set_on_create`, which sets the hook invoked with the type name of every [Cause] created by [Cause::new].
+//!   It requires `std`.
+//!
+//! # `no_std`
+//!
+//! [Cause] works without `std` as long as `alloc` is available, by disabling the default `std` feature.
+//! Then [Cause::with_backtrace], [Cause::with_timestamp], [Cause::msg_with], [Cause::catch], [Cause::report],
+//! [set_location_prefix], [set_location_hyperlink], [push_context], [set_formatter], [Cause::record_occurrence],
+//! [FromIoError] and the `Termination` implementation are not available.
 
-/// A macro to create a [Cause] which situationally appends filename and line number information at the end of message.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+/// A macro to create a [Cause] which situationally records the module path, filename, line and column number where it was created.
+///
+/// They are recorded only when compiled with `debug_assertions`, or with `release-location` feature,
+/// and never with `no-location` feature.
+///
+/// The message can be given with format arguments like [format!],
+/// and the source can be given after a semicolon.
+/// A literal message is formatted as well, so that it can capture the variables inline.
+///
+/// ```
+/// use cause::cause;
+/// # #[derive(Debug)]
+/// # enum ErrorType { NotFoundError }
+///
+/// let (id, table) = (42, "users");
+/// let cause = cause!(ErrorType::NotFoundError, "user {} not in {}", id, table);
+/// assert!(cause.message().unwrap().starts_with("user 42 not in users"));
+///
+/// let io_err = std::io::Error::other("oh no!");
+/// let cause = cause!(ErrorType::NotFoundError, "user {} not in {}", id, table; io_err);
+/// assert!(cause.message().unwrap().starts_with("user 42 not in users"));
+///
+/// let cause = cause!(ErrorType::NotFoundError, "user {id} not in {t}", t = table; std::fmt::Error);
+/// assert!(cause.message().unwrap().starts_with("user 42 not in users"));
+/// ```
 #[macro_export]
 macro_rules! cause {
     ($type:expr) => {
-        if cfg!(debug_assertions) {
-            $crate::Cause::new($type).msg(format!("[{}:{}]", file!(), line!()))
-        } else {
-            $crate::Cause::new($type)
-        }
+        $crate::__located!($crate::Cause::new($type))
+    };
+    ($type:expr, $msg:literal) => {
+        $crate::cause!($type, $crate::__format!($msg))
+    };
+    ($type:expr, $msg:literal; $src:expr) => {
+        $crate::cause!($type, $crate::__format!($msg); $src)
     };
     ($type:expr, $msg:expr) => {
-        if cfg!(debug_assertions) {
-            $crate::Cause::new($type).msg(format!("{} [{}:{}]", $msg, file!(), line!()))
+        $crate::__located!($crate::Cause::new($type).msg($msg))
+    };
+    ($type:expr, $msg:expr; $src:expr) => {
+        $crate::__located!($crate::Cause::new($type).msg($msg).src($src))
+    };
+    ($type:expr, $fmt:expr, $($arg:tt)+) => {
+        $crate::__cause_format!([$type] [$fmt] [] $($arg)+)
+    };
+}
+
+// Collects the format arguments up to the semicolon before the source, if any.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __cause_format {
+    ([$type:expr] [$fmt:expr] [$($arg:tt)*] ; $src:expr) => {
+        $crate::cause!($type, $crate::__format!($fmt, $($arg)*); $src)
+    };
+    ([$type:expr] [$fmt:expr] [$($arg:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::__cause_format!([$type] [$fmt] [$($arg)* $next] $($rest)*)
+    };
+    ([$type:expr] [$fmt:expr] [$($arg:tt)*]) => {
+        $crate::cause!($type, $crate::__format!($fmt, $($arg)*))
+    };
+}
+
+#[cfg(not(feature = "no-location"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __located {
+    ($cause:expr) => {
+        if cfg!(debug_assertions) || $crate::__RELEASE_LOCATION {
+            $cause.__location(module_path!(), file!(), line!(), column!())
         } else {
-            $crate::Cause::new($type).msg($msg)
+            $cause
+        }
+    };
+}
+
+// `file!()` is not expanded at all, so that the file names are not embedded in the binary.
+#[cfg(feature = "no-location")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __located {
+    ($cause:expr) => {
+        $cause
+    };
+}
+
+/// A macro to return early with an `Err` of the [Cause] created by the [cause] macro.
+///
+/// It takes the same arguments as the [cause] macro.
+///
+/// ```
+/// use cause::{bail, Cause};
+/// # #[derive(Debug)]
+/// # enum ErrorType { NotFoundError }
+///
+/// fn find(id: u32) -> Result<(), Cause<ErrorType>> {
+///     if id != 42 {
+///         bail!(ErrorType::NotFoundError, "user {} not found", id);
+///     }
+///     Ok(())
+/// }
+/// assert!(find(42).is_ok());
+/// assert!(find(7).is_err());
+/// ```
+#[macro_export]
+macro_rules! bail {
+    ($($arg:tt)+) => {
+        return ::core::result::Result::Err($crate::cause!($($arg)+))
+    };
+}
+
+/// A macro to return early with an `Err` of the [Cause] created by the [cause] macro, if the condition is not satisfied.
+///
+/// It takes the condition followed by the same arguments as the [cause] macro.
+///
+/// ```
+/// use cause::{ensure, Cause};
+/// # #[derive(Debug)]
+/// # enum ErrorType { InvalidArgumentsError }
+///
+/// fn set_age(age: u32) -> Result<(), Cause<ErrorType>> {
+///     ensure!(age < 200, ErrorType::InvalidArgumentsError, "age {} is too large", age);
+///     Ok(())
+/// }
+/// assert!(set_age(42).is_ok());
+/// assert!(set_age(999).is_err());
+/// ```
+#[macro_export]
+macro_rules! ensure {
+    ($cond:expr, $($arg:tt)+) => {
+        if !$cond {
+            $crate::bail!($($arg)+);
+        }
+    };
+}
+
+extern crate alloc;
+
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::any::Any;
+use core::error::Error;
+#[cfg(feature = "std")]
+use std::backtrace::{Backtrace, BacktraceStatus};
+#[cfg(feature = "std")]
+use std::sync::{Mutex, OnceLock};
+#[cfg(feature = "std")]
+use std::time::SystemTime;
+
+#[doc(hidden)]
+pub use alloc::format as __format;
+
+#[doc(hidden)]
+pub const __RELEASE_LOCATION: bool = cfg!(feature = "release-location");
+
+/// A tiny generic implementation of the [std::error::Error] trait.
+///
+/// The second type parameter `S` is the type of its source, `dyn Error + Send` by default.
+/// See also [LocalCause] and [SyncCause].
+pub struct Cause<T, S: ?Sized = dyn Error + Send + 'static> {
+    cause: T,
+    msg: Option<Message>,
+    src: Option<Box<S>>,
+    location: Option<Location>,
+    location_is_caller: bool,
+    module: Option<&'static str>,
+    #[cfg(feature = "std")]
+    backtrace: Option<Box<Backtrace>>,
+    details: Option<Box<Details<S>>>,
+}
+
+/// Attributes of a [Cause] which are rarely set, boxed to keep [Cause] small.
+#[derive(Debug)]
+struct Details<S: ?Sized> {
+    source_label: Option<&'static str>,
+    sub_errors: Vec<Box<S>>,
+    notes: Vec<String>,
+    fields: Vec<(&'static str, String)>,
+    help: Option<String>,
+    severity: Option<Severity>,
+    code: Option<u32>,
+    retryable: bool,
+    max_message_len: Option<usize>,
+    #[cfg(feature = "std")]
+    timestamp: Option<SystemTime>,
+}
+
+impl<S: ?Sized> Default for Details<S> {
+    fn default() -> Self {
+        Self {
+            source_label: None,
+            sub_errors: Vec::new(),
+            notes: Vec::new(),
+            fields: Vec::new(),
+            help: None,
+            severity: None,
+            code: None,
+            retryable: false,
+            max_message_len: None,
+            #[cfg(feature = "std")]
+            timestamp: None,
+        }
+    }
+}
+
+impl<S: ?Sized> Details<S> {
+    fn map_sub_errors<R: ?Sized>(self, f: impl FnMut(Box<S>) -> Box<R>) -> Details<R> {
+        Details {
+            source_label: self.source_label,
+            sub_errors: self.sub_errors.into_iter().map(f).collect(),
+            notes: self.notes,
+            fields: self.fields,
+            help: self.help,
+            severity: self.severity,
+            code: self.code,
+            retryable: self.retryable,
+            max_message_len: self.max_message_len,
+            #[cfg(feature = "std")]
+            timestamp: self.timestamp,
         }
+    }
+}
+
+// The sub errors are flattened the same as the source of a cloned `Cause`.
+impl<S: ?Sized + Source> Clone for Details<S> {
+    fn clone(&self) -> Self {
+        Self {
+            source_label: self.source_label,
+            sub_errors: self.sub_errors.iter().map(|e| S::from_sync(flatten_into_sync(e.as_error()))).collect(),
+            notes: self.notes.clone(),
+            fields: self.fields.clone(),
+            help: self.help.clone(),
+            severity: self.severity,
+            code: self.code,
+            retryable: self.retryable,
+            max_message_len: self.max_message_len,
+            #[cfg(feature = "std")]
+            timestamp: self.timestamp,
+        }
+    }
+}
+
+/// A [Cause] whose source doesn't have to be `Send`, set by [Cause::src_local].
+///
+/// It is not `Send` either, which is fine for single-threaded code.
+///
+/// ```
+/// use cause::{Cause, LocalCause};
+/// use std::error::Error;
+/// use std::rc::Rc;
+/// # #[derive(Debug)]
+/// # enum ErrorType { InternalError }
+///
+/// #[derive(Debug)]
+/// struct WidgetError(Rc<String>);
+/// impl std::fmt::Display for WidgetError {
+///     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+///         write!(f, "broken widget {}", self.0)
+///     }
+/// }
+/// impl Error for WidgetError {}
+///
+/// let cause: LocalCause<ErrorType> = Cause::new(ErrorType::InternalError)
+///     .src_local(WidgetError(Rc::new("button".to_string())));
+/// assert_eq!(cause.source().unwrap().to_string(), "broken widget button");
+/// ```
+pub type LocalCause<T> = Cause<T, dyn Error + 'static>;
+
+/// A [Cause] whose source has to be `Send` and `Sync`, set by [Cause::src_sync].
+///
+/// It is `Sync` as long as `T: Sync`, so that it can be stored in `Arc<Mutex<...>>` or `OnceLock`.
+///
+/// ```
+/// use cause::{Cause, SyncCause};
+/// use std::sync::OnceLock;
+/// # #[derive(Debug)]
+/// # enum ErrorType { InternalError }
+///
+/// static LAST_ERROR: OnceLock<SyncCause<ErrorType>> = OnceLock::new();
+///
+/// let io_err = std::io::Error::other("oh no!");
+/// LAST_ERROR.set(Cause::new(ErrorType::InternalError).src_sync(io_err)).unwrap();
+/// ```
+pub type SyncCause<T> = Cause<T, dyn Error + Send + Sync + 'static>;
+
+mod private {
+    pub trait Sealed {}
+    impl Sealed for dyn core::error::Error + Send + 'static {}
+    impl Sealed for dyn core::error::Error + 'static {}
+    impl Sealed for dyn core::error::Error + Send + Sync + 'static {}
+}
+
+// Replaces the wrapper of a source set by `src_shared` with the `Arc` it holds,
+// and that of a source set by `src_cloneable` with the error itself.
+fn unwrap_source<S: ?Sized + Source>(src: Box<S>) -> Box<S> {
+    if !src.as_error().is::<SharedError>() && !src.as_error().is::<CloneableSource>() {
+        return src;
+    }
+    let src = match src.into_sync().downcast::<SharedError>() {
+        Ok(shared) => return S::from_sync(Box::new(shared.0)),
+        Err(src) => src,
     };
+    match src.downcast::<CloneableSource>() {
+        Ok(cloneable) => S::from_sync(cloneable.0.into_error()),
+        Err(src) => S::from_sync(src),
+    }
+}
+
+/// The type of the source a [Cause] holds.
+///
+/// It is implemented only for `dyn Error + Send`, `dyn Error` and `dyn Error + Send + Sync`.
+pub trait Source: private::Sealed + Error + 'static {
+    #[doc(hidden)]
+    fn as_error(&self) -> &(dyn Error + 'static);
+
+    #[doc(hidden)]
+    fn from_sync(src: Box<dyn Error + Send + Sync + 'static>) -> Box<Self>;
+
+    #[doc(hidden)]
+    fn into_local(self: Box<Self>) -> Box<dyn Error + 'static>;
+
+    #[doc(hidden)]
+    fn into_sync(self: Box<Self>) -> Box<dyn Error + Send + Sync + 'static>;
+}
+
+// An error is flattened into a `StringError`, unless it is a `SharedError` or a `CloneableSource`.
+fn flatten_into_sync(src: &(dyn Error + 'static)) -> Box<dyn Error + Send + Sync + 'static> {
+    if let Some(shared) = src.downcast_ref::<SharedError>() {
+        Box::new(SharedError(shared.0.clone()))
+    } else if let Some(cloneable) = src.downcast_ref::<CloneableSource>() {
+        Box::new(CloneableSource(cloneable.0.clone_boxed()))
+    } else {
+        Box::new(StringError::new(src.to_string()))
+    }
+}
+
+impl Source for dyn Error + Send + 'static {
+    fn as_error(&self) -> &(dyn Error + 'static) {
+        self
+    }
+
+    fn from_sync(src: Box<dyn Error + Send + Sync + 'static>) -> Box<Self> {
+        src
+    }
+
+    fn into_local(self: Box<Self>) -> Box<dyn Error + 'static> {
+        self
+    }
+
+    fn into_sync(self: Box<Self>) -> Box<dyn Error + Send + Sync + 'static> {
+        flatten_into_sync(&*self)
+    }
+}
+
+impl Source for dyn Error + 'static {
+    fn as_error(&self) -> &(dyn Error + 'static) {
+        self
+    }
+
+    fn from_sync(src: Box<dyn Error + Send + Sync + 'static>) -> Box<Self> {
+        src
+    }
+
+    fn into_local(self: Box<Self>) -> Box<dyn Error + 'static> {
+        self
+    }
+
+    fn into_sync(self: Box<Self>) -> Box<dyn Error + Send + Sync + 'static> {
+        flatten_into_sync(&*self)
+    }
+}
+
+impl Source for dyn Error + Send + Sync + 'static {
+    fn as_error(&self) -> &(dyn Error + 'static) {
+        self
+    }
+
+    fn from_sync(src: Box<dyn Error + Send + Sync + 'static>) -> Box<Self> {
+        src
+    }
+
+    fn into_local(self: Box<Self>) -> Box<dyn Error + 'static> {
+        self
+    }
+
+    fn into_sync(self: Box<Self>) -> Box<dyn Error + Send + Sync + 'static> {
+        self
+    }
+}
+
+/// The place in the source code where a [Cause] was created.
+///
+/// It is recorded by the [cause] macro and [Cause::new] only when compiled with `debug_assertions`
+/// or with `release-location` feature, and never with `no-location` feature.
+/// The one given to [Cause::with_location] or [Cause::from_parts] is kept regardless of the features.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Location {
+    file: &'static str,
+    line: u32,
+    column: u32,
+}
+
+impl Location {
+
+    /// Create a [Location], e.g. to pass it to [Cause::from_parts].
+    pub const fn new(file: &'static str, line: u32, column: u32) -> Self {
+        Self { file, line, column }
+    }
+
+    /// Get the name of the source file
+    pub fn file(&self) -> &'static str {
+        self.file
+    }
+
+    /// Get the name of the source file without the prefix set by [set_location_prefix],
+    /// which is what [Display] renders.
+    pub fn short_file(&self) -> &'static str {
+        #[cfg(feature = "std")]
+        {
+            let prefix = *LOCATION_PREFIX.read().unwrap_or_else(|e| e.into_inner());
+            strip_path_prefix(self.file, prefix)
+        }
+        #[cfg(not(feature = "std"))]
+        {
+            self.file
+        }
+    }
+
+    /// Get the line number
+    pub fn line(&self) -> u32 {
+        self.line
+    }
+
+    /// Get the column number
+    pub fn column(&self) -> u32 {
+        self.column
+    }
 }
 
-use std::error::Error;
+impl core::fmt::Display for Location {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> Result<(), core::fmt::Error> {
+        write!(f, "{}:{}:{}", self.short_file(), self.line, self.column)
+    }
+}
+
+// Renders a location as the OSC 8 hyperlink set by `set_location_hyperlink`, only for the human-facing output.
+struct LocationLink<'a>(&'a Location);
+
+impl core::fmt::Display for LocationLink<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> Result<(), core::fmt::Error> {
+        #[cfg(feature = "std")]
+        {
+            let template = *LOCATION_HYPERLINK.read().unwrap_or_else(|e| e.into_inner());
+            if !template.is_empty() && !PLAIN_LOCATIONS.try_with(|c| c.get()).unwrap_or(true) {
+                let url = template
+                    .replace("{file}", self.0.file)
+                    .replace("{line}", &self.0.line.to_string())
+                    .replace("{column}", &self.0.column.to_string());
+                return write!(f, "\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, self.0);
+            }
+        }
+        write!(f, "{}", self.0)
+    }
+}
+
+// Removes `prefix` from `file` only on a path component boundary, so that
+// prefix `sr` leaves `src/x.rs` untouched.
+#[cfg(feature = "std")]
+fn strip_path_prefix(file: &'static str, prefix: &str) -> &'static str {
+    if !prefix.is_empty() {
+        if let Some(rest) = file.strip_prefix(prefix) {
+            if rest.is_empty() || rest.starts_with(['/', '\\']) || prefix.ends_with(['/', '\\']) {
+                return rest.trim_start_matches(['/', '\\']);
+            }
+        }
+    }
+    file
+}
+
+#[cfg(feature = "std")]
+std::thread_local! {
+    static PLAIN_LOCATIONS: core::cell::Cell<bool> = const { core::cell::Cell::new(false) };
+}
+
+// Runs `render` with `LocationLink` rendering plain text, so that the sources rendered inside it,
+// which may be other `Cause`s, don't emit hyperlinks either.
+fn plain_locations<R>(render: impl FnOnce() -> R) -> R {
+    #[cfg(feature = "std")]
+    {
+        struct Restore(bool);
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                let _ = PLAIN_LOCATIONS.try_with(|c| c.set(self.0));
+            }
+        }
+        let _restore = Restore(PLAIN_LOCATIONS.try_with(|c| c.replace(true)).unwrap_or(true));
+        render()
+    }
+    #[cfg(not(feature = "std"))]
+    {
+        render()
+    }
+}
+
+#[cfg(feature = "std")]
+static LOCATION_PREFIX: std::sync::RwLock<&'static str> = std::sync::RwLock::new("");
+
+/// Set the prefix stripped from the file names of [Location]s when they are rendered,
+/// e.g. the root directory of the workspace. Nothing is stripped by default.
+///
+/// ```
+/// use cause::cause;
+/// # #[derive(Debug)]
+/// # enum ErrorType { InternalError }
+///
+/// cause::set_location_prefix("src");
+/// let cause = cause!(ErrorType::InternalError);
+/// if cause.location().is_some() {
+///     assert!(cause.to_string().starts_with("InternalError: [lib.rs:"));
+///     assert!(cause.location().unwrap().file().starts_with("src/"));
+/// }
+/// ```
+#[cfg(feature = "std")]
+pub fn set_location_prefix(prefix: &'static str) {
+    *LOCATION_PREFIX.write().unwrap_or_else(|e| e.into_inner()) = prefix;
+}
+
+#[cfg(feature = "std")]
+static LOCATION_HYPERLINK: std::sync::RwLock<&'static str> = std::sync::RwLock::new("");
+
+/// Set the URL template which [Location]s are rendered as OSC 8 hyperlinks to by [Display] of [Cause],
+/// so that they are clickable in terminals supporting them. They are rendered as plain text by default.
+///
+/// [Display] of [Location] itself, [Debug] of [Cause], [Cause::report], [Cause::to_single_line],
+/// `Cause::to_json_string` and `Cause::to_problem_json` always render plain text, including the sources in the chain.
+///
+/// `{file}`, `{line}` and `{column}` in the template are replaced with those of the location,
+/// and an empty template turns the hyperlinks off again.
+///
+/// ```
+/// use cause::{cause, Cause};
+/// # #[derive(Debug)]
+/// # enum ErrorType { InternalError, NotFoundError }
+///
+/// cause::set_location_hyperlink(concat!("vscode://file/", env!("CARGO_MANIFEST_DIR"), "/{file}:{line}:{column}"));
+/// let cause = cause!(ErrorType::InternalError);
+/// if let Some(location) = cause.location() {
+///     assert!(cause.to_string().starts_with("InternalError: [\x1b]8;;vscode://file/"));
+///     assert_eq!(cause.to_single_line(), format!("InternalError: [{}]", location));
+/// }
+///
+/// let cause = Cause::new(ErrorType::InternalError).src(cause!(ErrorType::NotFoundError, "inner"));
+/// if cause.find::<Cause<ErrorType>>().unwrap().location().is_some() {
+///     assert!(cause.to_string().contains('\x1b'));
+///     assert!(!format!("{:?}", cause).contains("\\u{1b}"));
+///     let mut report = Vec::new();
+///     cause.report(&mut report).unwrap();
+///     assert!(!report.contains(&0x1b));
+///     assert!(!cause.to_single_line().contains('\x1b'));
+///     # #[cfg(feature = "json")]
+///     # {
+///     assert!(!cause.to_json_string().contains("\\u001b"));
+///     assert!(!cause.to_problem_json(None).contains("\\u001b"));
+///     # }
+/// }
+/// ```
+#[cfg(feature = "std")]
+pub fn set_location_hyperlink(template: &'static str) {
+    *LOCATION_HYPERLINK.write().unwrap_or_else(|e| e.into_inner()) = template;
+}
+
+#[cfg(feature = "on-create")]
+type OnCreate = dyn Fn(&'static str, Option<&Location>) + Send + Sync;
+
+#[cfg(feature = "on-create")]
+static ON_CREATE: std::sync::RwLock<Option<Arc<OnCreate>>> = std::sync::RwLock::new(None);
+
+/// Set the hook invoked for every [Cause] created by [Cause::new], and so by the [cause] macro,
+/// [ResultExt], [OptionExt] and the `From` conversions, e.g. to count the errors by their types.
+/// It replaces the previously set one.
+///
+/// The hook is given the type name of the `cause` by [core::any::type_name] and the location recorded by [Cause::new].
+/// It can tell the types of the errors apart but not their variants, since [Cause::new] doesn't require the `cause` to be [Debug].
+///
+/// ```
+/// use cause::Cause;
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// # #[derive(Debug)]
+/// # enum ErrorType { InternalError }
+///
+/// static CREATED: AtomicUsize = AtomicUsize::new(0);
+///
+/// cause::set_on_create(Box::new(|type_name, _location| {
+///     if type_name.ends_with("::ErrorType") {
+///         CREATED.fetch_add(1, Ordering::Relaxed);
+///     }
+/// }));
+/// let _ = cause::cause!(ErrorType::InternalError, "oops");
+/// let _ = Cause::new(ErrorType::InternalError);
+/// assert_eq!(CREATED.load(Ordering::Relaxed), 2);
+///
+/// // A hook may create causes and replace itself without deadlocking.
+/// cause::set_on_create(Box::new(|_, _| {
+///     let _ = Cause::new(ErrorType::InternalError);
+///     cause::set_on_create(Box::new(|_, _| {}));
+/// }));
+/// let _ = Cause::new(ErrorType::InternalError);
+/// ```
+#[cfg(feature = "on-create")]
+pub fn set_on_create(hook: Box<OnCreate>) {
+    *ON_CREATE.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::from(hook));
+}
+
+#[cfg(feature = "on-create")]
+std::thread_local! {
+    static IN_ON_CREATE: core::cell::Cell<bool> = const { core::cell::Cell::new(false) };
+}
+
+// The lock is released before the hook is invoked, so that the hook can create a `Cause` or set another hook.
+// The causes created by the hook itself don't invoke it again.
+#[cfg(feature = "on-create")]
+fn on_create<T>(location: Option<&Location>) {
+    if IN_ON_CREATE.try_with(|c| c.replace(true)).unwrap_or(true) {
+        return;
+    }
+    struct Reset;
+    impl Drop for Reset {
+        fn drop(&mut self) {
+            let _ = IN_ON_CREATE.try_with(|c| c.set(false));
+        }
+    }
+    let _reset = Reset;
+    let hook = ON_CREATE.read().unwrap_or_else(|e| e.into_inner()).clone();
+    if let Some(hook) = hook {
+        hook(core::any::type_name::<T>(), location);
+    }
+}
+
+#[cfg(feature = "std")]
+std::thread_local! {
+    static CONTEXT: core::cell::RefCell<Vec<(&'static str, String)>> = const { core::cell::RefCell::new(Vec::new()) };
+}
+
+/// Push a key-value field to the context of the current thread,
+/// which is attached to every [Cause] created on it until the returned guard is dropped.
+///
+/// ```
+/// use cause::Cause;
+/// # #[derive(Debug)]
+/// # enum ErrorType { InternalError }
+///
+/// {
+///     let _guard = cause::push_context("request_id", "abc");
+///     let cause = Cause::new(ErrorType::InternalError).field("retries", "3");
+///     assert_eq!(cause.fields(), [("request_id", "abc".to_string()), ("retries", "3".to_string())]);
+/// }
+/// assert!(Cause::new(ErrorType::InternalError).fields().is_empty());
+/// ```
+#[cfg(feature = "std")]
+pub fn push_context(key: &'static str, value: impl Into<String>) -> ContextGuard {
+    let depth = CONTEXT.with(|context| {
+        let mut context = context.borrow_mut();
+        context.push((key, value.into()));
+        context.len() - 1
+    });
+    ContextGuard { depth, _not_send: core::marker::PhantomData }
+}
+
+/// A guard returned by [push_context], which pops the field from the context when dropped.
+///
+/// The fields pushed after it are popped together.
+#[cfg(feature = "std")]
+#[must_use]
+pub struct ContextGuard {
+    depth: usize,
+    _not_send: core::marker::PhantomData<*const ()>,
+}
+
+#[cfg(feature = "std")]
+impl Drop for ContextGuard {
+    fn drop(&mut self) {
+        let _ = CONTEXT.try_with(|context| context.borrow_mut().truncate(self.depth));
+    }
+}
+
+#[cfg(feature = "std")]
+fn context_details<S: ?Sized>() -> Option<Box<Details<S>>> {
+    CONTEXT
+        .try_with(|context| {
+            let context = context.borrow();
+            if context.is_empty() {
+                return None;
+            }
+            Some(Box::new(Details { fields: context.clone(), ..Default::default() }))
+        })
+        .ok()
+        .flatten()
+}
+
+#[cfg(feature = "std")]
+type CauseFormatter = Arc<dyn Fn(&dyn Any, &mut core::fmt::Formatter) -> Result<(), core::fmt::Error> + Send + Sync>;
+
+#[cfg(feature = "std")]
+static FORMATTERS: std::sync::RwLock<Vec<(core::any::TypeId, CauseFormatter)>> = std::sync::RwLock::new(Vec::new());
+
+// Set once any formatter is set, so that `Display` doesn't take the lock of `FORMATTERS` until then.
+#[cfg(feature = "std")]
+static HAS_FORMATTERS: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+/// Set the formatter which [Display] renders the `cause`s of type `T` with instead of [Debug],
+/// e.g. for a foreign type whose [Debug] is not readable. It replaces the previously set one for `T`.
+///
+/// ```
+/// use cause::Cause;
+/// # #[derive(Debug)]
+/// # struct ForeignError { code: i32 }
+///
+/// cause::set_formatter::<ForeignError>(|e, f| write!(f, "ForeignError({})", e.code));
+/// let cause = Cause::new(ForeignError { code: 42 }).msg("oops");
+/// assert_eq!(cause.to_string(), "ForeignError(42): oops");
+///
+/// // A formatter may render another `Cause` and set another formatter.
+/// # #[derive(Debug)]
+/// # struct Wrapper(u8);
+/// cause::set_formatter::<Wrapper>(|w, f| {
+///     cause::set_formatter::<u8>(|n, f| write!(f, "#{}", n));
+///     write!(f, "Wrapper({})", Cause::new(w.0))
+/// });
+/// assert_eq!(Cause::new(Wrapper(1)).to_string(), "Wrapper(#1)");
+/// ```
+#[cfg(feature = "std")]
+pub fn set_formatter<T: 'static>(
+    formatter: impl Fn(&T, &mut core::fmt::Formatter) -> Result<(), core::fmt::Error> + Send + Sync + 'static,
+) {
+    let id = core::any::TypeId::of::<T>();
+    let formatter: CauseFormatter = Arc::new(move |cause, f| match cause.downcast_ref::<T>() {
+        Some(cause) => formatter(cause, f),
+        None => Err(core::fmt::Error),
+    });
+    let mut formatters = FORMATTERS.write().unwrap_or_else(|e| e.into_inner());
+    formatters.retain(|(t, _)| *t != id);
+    formatters.push((id, formatter));
+    HAS_FORMATTERS.store(true, std::sync::atomic::Ordering::Release);
+}
+
+/// How severe a [Cause] is, [Severity::Error] by default.
+///
+/// The variants are ordered from the least severe to the most severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Severity {
+    Trace,
+    Info,
+    Warning,
+    #[default]
+    Error,
+    Fatal,
+}
+
+impl Severity {
+
+    /// Get the RFC 5424 syslog severity value of this severity.
+    ///
+    /// | [Severity] | syslog severity   |
+    /// |------------|-------------------|
+    /// | `Fatal`    | 2 (Critical)      |
+    /// | `Error`    | 3 (Error)         |
+    /// | `Warning`  | 4 (Warning)       |
+    /// | `Info`     | 6 (Informational) |
+    /// | `Trace`    | 7 (Debug)         |
+    pub fn syslog_severity(self) -> u8 {
+        match self {
+            Severity::Trace => 7,
+            Severity::Info => 6,
+            Severity::Warning => 4,
+            Severity::Error => 3,
+            Severity::Fatal => 2,
+        }
+    }
+}
+
+impl<T> Cause<T> {
+
+    /// Create a [Cause] instance with its `cause`.
+    ///
+    /// When compiled with `debug_assertions` or with `release-location` feature, and without `no-location` feature,
+    /// it records the location of its caller, which is available through [Cause::location]
+    /// but not rendered by [Display] unlike the one recorded by the [cause] macro.
+    ///
+    /// The fields pushed by [push_context] on the current thread are attached to it.
+    #[cfg_attr(not(feature = "no-location"), track_caller)]
+    pub fn new(cause: T) -> Self {
+        let location = if (cfg!(debug_assertions) || __RELEASE_LOCATION) && !cfg!(feature = "no-location") {
+            let caller = core::panic::Location::caller();
+            Some(Location { file: caller.file(), line: caller.line(), column: caller.column() })
+        } else {
+            None
+        };
+        #[cfg(feature = "on-create")]
+        on_create::<T>(location.as_ref());
+        Self {
+            cause,
+            msg: None,
+            src: None,
+            location,
+            location_is_caller: location.is_some(),
+            module: None,
+            #[cfg(feature = "std")]
+            backtrace: None,
+            #[cfg(feature = "std")]
+            details: context_details(),
+            #[cfg(not(feature = "std"))]
+            details: None,
+        }
+    }
+
+    /// Create a [Cause] instance from its parts, e.g. to reconstruct an error from another representation.
+    ///
+    /// The location is rendered by [Display] the same as the one set by [Cause::with_location].
+    ///
+    /// ```
+    /// use cause::{Cause, Location};
+    /// # #[derive(Debug)]
+    /// # enum ErrorType { InternalError }
+    ///
+    /// let cause = Cause::from_parts(ErrorType::InternalError, Some("oops".to_string()), Some(Location::new("main.rs", 1, 2)));
+    /// assert_eq!(cause.to_string(), "InternalError: oops [main.rs:1:2]");
+    /// ```
+    pub fn from_parts(cause: T, msg: Option<String>, location: Option<Location>) -> Self {
+        Self {
+            cause,
+            msg: msg.map(|m| Message::Text(Cow::Owned(m))),
+            src: None,
+            location,
+            location_is_caller: false,
+            module: None,
+            #[cfg(feature = "std")]
+            backtrace: None,
+            details: None,
+        }
+    }
+
+    /// Create a [Cause] instance which holds several independent errors, e.g. validation errors.
+    ///
+    /// Unlike the source, the sub errors don't make a chain, and they are rendered as a numbered list by [Display].
+    ///
+    /// ```
+    /// use cause::{Cause, StringError};
+    /// use std::error::Error;
+    /// # #[derive(Debug)]
+    /// # enum ErrorType { ValidationError }
+    ///
+    /// let errors: Vec<Box<dyn Error + Send>> = vec![
+    ///     Box::new(StringError::new("name is empty")),
+    ///     Box::new(StringError::new("age is negative")),
+    /// ];
+    /// let cause = Cause::aggregate(ErrorType::ValidationError, errors);
+    /// assert_eq!(cause.sub_errors().len(), 2);
+    /// assert_eq!(
+    ///     cause.to_string(),
+    ///     "ValidationError\n\nErrors:\n    0: name is empty\n    1: age is negative"
+    /// );
+    /// ```
+    #[cfg_attr(not(feature = "no-location"), track_caller)]
+    pub fn aggregate(cause: T, errors: impl IntoIterator<Item = Box<dyn Error + Send + 'static>>) -> Self {
+        let mut aggregate = Self::new(cause);
+        aggregate.details_mut().sub_errors = errors.into_iter().collect();
+        aggregate
+    }
+
+    /// Collect all the `Ok` values of `iter`, or all the `Err`s into a [Cause] made by [Cause::aggregate]
+    /// if any of them failed, instead of stopping at the first one like `collect::<Result<_, _>>()`.
+    ///
+    /// ```
+    /// use cause::{Cause, StringError};
+    /// # #[derive(Debug)]
+    /// # enum ErrorType { ValidationError }
+    ///
+    /// let parse = |s: &str| s.parse::<u8>().map_err(|_| StringError::new(format!("invalid number {:?}", s)));
+    ///
+    /// let numbers = Cause::try_collect_all(ErrorType::ValidationError, vec!["1", "2"].into_iter().map(parse));
+    /// assert_eq!(numbers.unwrap(), [1, 2]);
+    ///
+    /// let cause = Cause::try_collect_all(ErrorType::ValidationError, vec!["1", "x", "y"].into_iter().map(parse)).unwrap_err();
+    /// assert_eq!(cause.sub_errors().len(), 2);
+    /// assert_eq!(cause.sub_errors()[0].to_string(), "invalid number \"x\"");
+    /// ```
+    #[cfg_attr(not(feature = "no-location"), track_caller)]
+    pub fn try_collect_all<U, E, I>(cause: T, iter: I) -> Result<Vec<U>, Self>
+    where
+        E: Error + Send + 'static,
+        I: IntoIterator<Item = Result<U, E>>,
+    {
+        let mut values = Vec::new();
+        let mut errors: Vec<Box<dyn Error + Send + 'static>> = Vec::new();
+        for item in iter {
+            match item {
+                Ok(value) => values.push(value),
+                Err(e) => errors.push(Box::new(e)),
+            }
+        }
+        if errors.is_empty() {
+            Ok(values)
+        } else {
+            Err(Self::aggregate(cause, errors))
+        }
+    }
+
+    /// Create a [Cause] instance from the payload of a caught panic,
+    /// whose message is the panic message if the payload is a `&str` or a `String`.
+    ///
+    /// ```
+    /// use cause::Cause;
+    /// # #[derive(Debug)]
+    /// # enum ErrorType { PluginError }
+    ///
+    /// let payload = std::panic::catch_unwind(|| panic!("oh no!")).unwrap_err();
+    /// let cause = Cause::from_panic(ErrorType::PluginError, payload);
+    /// assert_eq!(cause.message(), Some("oh no!"));
+    /// ```
+    #[cfg_attr(not(feature = "no-location"), track_caller)]
+    pub fn from_panic(cause: T, payload: Box<dyn Any + Send + 'static>) -> Self {
+        let cause = Self::new(cause);
+        match payload.downcast::<String>() {
+            Ok(msg) => cause.msg(*msg),
+            Err(payload) => match payload.downcast_ref::<&'static str>() {
+                Some(msg) => cause.msg_static(msg),
+                None => cause,
+            },
+        }
+    }
+
+    /// Run `f`, converting a panic in it into a [Cause] by [Cause::from_panic].
+    ///
+    /// The panic hook is still called as usual, e.g. the panic message is printed to the standard error.
+    ///
+    /// ```
+    /// use cause::Cause;
+    /// # #[derive(Debug)]
+    /// # enum ErrorType { PluginError }
+    ///
+    /// assert_eq!(Cause::catch(ErrorType::PluginError, || 42).unwrap(), 42);
+    /// let cause = Cause::catch(ErrorType::PluginError, || -> u32 { panic!("bad plugin {}", 7) }).unwrap_err();
+    /// assert_eq!(cause.message(), Some("bad plugin 7"));
+    /// ```
+    #[cfg(feature = "std")]
+    #[cfg_attr(not(feature = "no-location"), track_caller)]
+    pub fn catch<R, F: FnOnce() -> R + std::panic::UnwindSafe>(cause: T, f: F) -> Result<R, Self> {
+        match std::panic::catch_unwind(f) {
+            Ok(r) => Ok(r),
+            Err(payload) => Err(Self::from_panic(cause, payload)),
+        }
+    }
+
+    /// Set the lower-level source of this error, if any.
+    pub fn src(mut self, src: impl Error + Send + 'static) -> Self {
+        self.src = Some(Box::new(src));
+        self
+    }
+}
+
+impl<T> LocalCause<T> {
+
+    /// Set the lower-level source of this error, if any.
+    pub fn src(mut self, src: impl Error + 'static) -> Self {
+        self.src = Some(Box::new(src));
+        self
+    }
+}
+
+impl<T> SyncCause<T> {
+
+    /// Set the lower-level source of this error, if any.
+    pub fn src(mut self, src: impl Error + Send + Sync + 'static) -> Self {
+        self.src = Some(Box::new(src));
+        self
+    }
+}
+
+impl<T, S: ?Sized + Source> Cause<T, S> {
+
+    /// Create a [Cause] instance with its `cause` in a `const` context, e.g. for a table of fixed errors.
+    ///
+    /// Unlike [Cause::new], it records no location and attaches no context of [push_context].
+    /// A `static` has to be a [SyncCause], since a [Cause] is not `Sync`.
+    ///
+    /// ```
+    /// use cause::{Cause, SyncCause};
+    /// # #[derive(Debug, PartialEq)]
+    /// # enum ErrorType { NotFoundError }
+    ///
+    /// const NOT_FOUND: Cause<ErrorType> = Cause::new_const(ErrorType::NotFoundError);
+    /// static SYNC_NOT_FOUND: SyncCause<ErrorType> = SyncCause::new_const(ErrorType::NotFoundError);
+    ///
+    /// fn find() -> Result<(), Cause<ErrorType>> {
+    ///     Err(NOT_FOUND.msg("no such user"))
+    /// }
+    /// assert_eq!(find().unwrap_err().to_string(), "NotFoundError: no such user");
+    /// assert_eq!(SYNC_NOT_FOUND.to_string(), "NotFoundError");
+    /// ```
+    pub const fn new_const(cause: T) -> Self {
+        Self {
+            cause,
+            msg: None,
+            src: None,
+            location: None,
+            location_is_caller: false,
+            module: None,
+            #[cfg(feature = "std")]
+            backtrace: None,
+            details: None,
+        }
+    }
+
+    /// Set the message for this error.
+    pub fn msg(mut self, msg: impl Into<String>) -> Self {
+        self.msg = Some(Message::Text(Cow::Owned(msg.into())));
+        self
+    }
+
+    /// Set the static message for this error, which doesn't allocate.
+    pub fn msg_static(mut self, msg: &'static str) -> Self {
+        self.msg = Some(Message::Text(Cow::Borrowed(msg)));
+        self
+    }
+
+    /// Set the message for this error, which is built by `f` only when it is needed,
+    /// i.e. when this error is displayed or [Cause::message] is called.
+    ///
+    /// ```
+    /// use cause::Cause;
+    /// # #[derive(Debug)]
+    /// # enum ErrorType { InternalError }
+    ///
+    /// let payload = vec![1, 2, 3];
+    /// let cause = Cause::new(ErrorType::InternalError).msg_with(move || format!("bad payload {:?}", payload));
+    /// assert_eq!(cause.message(), Some("bad payload [1, 2, 3]"));
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn msg_with<F: FnOnce() -> String + Send + 'static>(mut self, f: F) -> Self {
+        self.msg = Some(Message::Lazy(Box::new(LazyMessage {
+            init: Mutex::new(Some(Box::new(f))),
+            value: OnceLock::new(),
+        })));
+        self
+    }
+
+    /// Set the lower-level source of this error which doesn't have to be `Send`.
+    ///
+    /// It turns this error into a [LocalCause], which is not `Send` either.
+    pub fn src_local(self, src: impl Error + 'static) -> LocalCause<T> {
+        self.replace_source(Some(Box::new(src)), S::into_local)
+    }
+
+    /// Set the lower-level source of this error which is `Send` and `Sync`.
+    ///
+    /// It turns this error into a [SyncCause], which is `Sync` as long as `T: Sync`.
+    pub fn src_sync(self, src: impl Error + Send + Sync + 'static) -> SyncCause<T> {
+        self.replace_source(Some(Box::new(src)), S::into_sync)
+    }
+
+    // The source is flattened the same as the one of a cloned `Cause`, unless it is `Sync` already.
+    #[cfg(feature = "std")]
+    fn into_sync_cause(mut self) -> SyncCause<T> {
+        let src = self.src.take().map(S::into_sync);
+        self.replace_source(src, S::into_sync)
+    }
+
+    fn replace_source<R: ?Sized>(self, src: Option<Box<R>>, convert: fn(Box<S>) -> Box<R>) -> Cause<T, R> {
+        Cause {
+            cause: self.cause,
+            msg: self.msg,
+            src,
+            location: self.location,
+            location_is_caller: self.location_is_caller,
+            module: self.module,
+            #[cfg(feature = "std")]
+            backtrace: self.backtrace,
+            details: self.details.map(|d| Box::new(d.map_sub_errors(convert))),
+        }
+    }
+
+    /// Set the lower-level source of this error which is shared with others.
+    ///
+    /// Unlike the other sources, it is kept as it is when this error is cloned.
+    pub fn src_shared(mut self, src: Arc<dyn Error + Send + Sync + 'static>) -> Self {
+        self.src = Some(S::from_sync(Box::new(SharedError(src))));
+        self
+    }
+
+    /// Set the lower-level source of this error which can be cloned.
+    ///
+    /// Unlike the other sources, it is cloned as it is when this error is cloned.
+    ///
+    /// ```
+    /// use cause::Cause;
+    /// # #[derive(Debug, Clone)]
+    /// # enum ErrorType { InternalError }
+    ///
+    /// let cause = Cause::new(ErrorType::InternalError).src_cloneable(std::fmt::Error);
+    /// assert!(cause.clone().find::<std::fmt::Error>().is_some());
+    /// ```
+    pub fn src_cloneable(mut self, src: impl Error + Clone + Send + Sync + 'static) -> Self {
+        self.src = Some(S::from_sync(Box::new(CloneableSource(Box::new(src)))));
+        self
+    }
+
+    /// Set the lower-level source of this error which is already boxed.
+    pub fn src_boxed(mut self, src: Box<S>) -> Self {
+        self.src = Some(src);
+        self
+    }
+
+    /// Capture a backtrace for this error.
+    ///
+    /// It follows [Backtrace::capture], so nothing is captured unless
+    /// `RUST_BACKTRACE` or `RUST_LIB_BACKTRACE` environment variable enables it.
+    #[cfg(feature = "std")]
+    pub fn with_backtrace(mut self) -> Self {
+        self.backtrace = Some(Box::new(Backtrace::capture()));
+        self
+    }
+
+    /// Record the current time as the time this error was created.
+    ///
+    /// It is rendered by [Cause::report_verbose] as seconds since the Unix epoch.
+    ///
+    /// ```
+    /// use cause::Cause;
+    /// # #[derive(Debug)]
+    /// # enum ErrorType { InternalError }
+    ///
+    /// let cause = Cause::new(ErrorType::InternalError).with_timestamp();
+    /// assert!(cause.timestamp().unwrap() <= std::time::SystemTime::now());
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn with_timestamp(mut self) -> Self {
+        self.details_mut().timestamp = Some(SystemTime::now());
+        self
+    }
+
+    /// Set the severity of this error, [Severity::Error] by default.
+    ///
+    /// ```
+    /// use cause::{Cause, Severity};
+    /// # #[derive(Debug)]
+    /// # enum ErrorType { InternalError }
+    ///
+    /// assert_eq!(Cause::new(ErrorType::InternalError).severity(), Severity::Error);
+    ///
+    /// let cause = Cause::new(ErrorType::InternalError).with_severity(Severity::Warning);
+    /// assert_eq!(cause.severity(), Severity::Warning);
+    /// ```
+    pub fn with_severity(mut self, severity: Severity) -> Self {
+        self.details_mut().severity = Some(severity);
+        self
+    }
+
+    /// Set the numeric error code of this error, which [Display] renders as a prefix like `[E0042]`.
+    ///
+    /// ```
+    /// use cause::Cause;
+    /// # #[derive(Debug)]
+    /// # enum ErrorType { InternalError }
+    ///
+    /// let cause = Cause::new(ErrorType::InternalError).msg("oops").with_code(42);
+    /// assert_eq!(cause.code(), Some(42));
+    /// assert_eq!(cause.to_string(), "[E0042] InternalError: oops");
+    /// ```
+    pub fn with_code(mut self, code: u32) -> Self {
+        self.details_mut().code = Some(code);
+        self
+    }
+
+    /// Set whether this error is transient and worth retrying, `false` by default.
+    ///
+    /// ```
+    /// use cause::Cause;
+    /// # #[derive(Debug)]
+    /// # enum ErrorType { TimeoutError }
+    ///
+    /// assert!(!Cause::new(ErrorType::TimeoutError).is_retryable());
+    /// assert!(Cause::new(ErrorType::TimeoutError).retryable(true).is_retryable());
+    /// ```
+    pub fn retryable(mut self, yes: bool) -> Self {
+        if yes || self.details.is_some() {
+            self.details_mut().retryable = yes;
+        }
+        self
+    }
+
+    /// Limit the message rendered by [Display] to `n` characters, followed by `…` when it is longer.
+    ///
+    /// The whole message is still available through [Cause::message].
+    ///
+    /// ```
+    /// use cause::Cause;
+    /// # #[derive(Debug)]
+    /// # enum ErrorType { InternalError }
+    ///
+    /// let cause = Cause::new(ErrorType::InternalError).msg("invalid payload: ünïcödé").truncate_to(19);
+    /// assert_eq!(cause.to_string(), "InternalError: invalid payload: ün…");
+    /// assert_eq!(cause.message(), Some("invalid payload: ünïcödé"));
+    /// ```
+    pub fn truncate_to(mut self, n: usize) -> Self {
+        self.details_mut().max_message_len = Some(n);
+        self
+    }
+
+    /// Set the label put before the source in [Display], `"Caused by:"` by default.
+    pub fn with_source_label(mut self, label: &'static str) -> Self {
+        self.details_mut().source_label = Some(label);
+        self
+    }
+
+    /// Append a note, an auxiliary context of this error.
+    ///
+    /// ```
+    /// use cause::Cause;
+    /// # #[derive(Debug)]
+    /// # enum ErrorType { InternalError }
+    ///
+    /// let cause = Cause::new(ErrorType::InternalError).note("while saving").note("retried 3 times");
+    /// assert_eq!(cause.notes(), ["while saving", "retried 3 times"]);
+    /// assert_eq!(cause.to_string(), "InternalError\n\nNotes:\n    - while saving\n    - retried 3 times");
+    /// ```
+    pub fn note(mut self, note: impl Into<String>) -> Self {
+        self.details_mut().notes.push(note.into());
+        self
+    }
+
+    /// Attach a key-value field, a piece of structured context of this error.
+    ///
+    /// Fields keep the order they were added in.
+    ///
+    /// ```
+    /// use cause::Cause;
+    /// # #[derive(Debug)]
+    /// # enum ErrorType { InternalError }
+    ///
+    /// let cause = Cause::new(ErrorType::InternalError).field("request_id", "abc").field("retries", 3.to_string());
+    /// assert_eq!(cause.fields(), [("request_id", "abc".to_string()), ("retries", "3".to_string())]);
+    /// assert_eq!(cause.to_string(), "InternalError\n\nFields:\n    request_id=abc\n    retries=3");
+    /// ```
+    pub fn field(mut self, key: &'static str, value: impl Into<String>) -> Self {
+        self.details_mut().fields.push((key, value.into()));
+        self
+    }
+
+    /// Set a help message, a suggestion for how to fix this error
+    ///
+    /// ```
+    /// use cause::Cause;
+    /// # #[derive(Debug)]
+    /// # enum ErrorType { InternalError }
+    ///
+    /// let cause = Cause::new(ErrorType::InternalError).help("try running with --force");
+    /// assert_eq!(cause.help_text(), Some("try running with --force"));
+    /// assert_eq!(cause.to_string(), "InternalError\n\nhelp: try running with --force");
+    /// ```
+    pub fn help(mut self, text: impl Into<String>) -> Self {
+        self.details_mut().help = Some(text.into());
+        self
+    }
+
+    /// Rewrite the message, the notes, the values of the fields and the help message of this error with `f`,
+    /// e.g. to scrub sensitive data before the error is rendered. The sources are left as they are.
+    ///
+    /// ```
+    /// use cause::Cause;
+    /// # #[derive(Debug)]
+    /// # enum ErrorType { AuthError }
+    ///
+    /// let cause = Cause::new(ErrorType::AuthError)
+    ///     .msg("invalid token abc123")
+    ///     .field("token", "abc123")
+    ///     .redact(|s| s.replace("abc123", "***"));
+    /// assert_eq!(cause.to_string(), "AuthError: invalid token ***\n\nFields:\n    token=***");
+    /// ```
+    pub fn redact(mut self, f: impl Fn(&str) -> String) -> Self {
+        if let Some(message) = self.message() {
+            self.msg = Some(Message::Text(Cow::Owned(f(message))));
+        }
+        if let Some(details) = &mut self.details {
+            for note in &mut details.notes {
+                *note = f(note);
+            }
+            for (_, value) in &mut details.fields {
+                *value = f(value);
+            }
+            if let Some(help) = &mut details.help {
+                *help = f(help);
+            }
+        }
+        self
+    }
+
+    fn details_mut(&mut self) -> &mut Details<S> {
+        self.details.get_or_insert_with(Default::default)
+    }
+
+    /// Set the location where this error was created, the same as the one recorded by the [cause] macro,
+    /// e.g. to attribute the error to the logical source of generated code.
+    ///
+    /// ```
+    /// use cause::Cause;
+    /// # #[derive(Debug)]
+    /// # enum ErrorType { InternalError }
+    ///
+    /// let cause = Cause::new(ErrorType::InternalError).with_location("schema.sql", 12, 5);
+    /// assert_eq!(cause.location().unwrap().file(), "schema.sql");
+    /// assert_eq!(cause.to_string(), "InternalError: [schema.sql:12:5]");
+    /// ```
+    pub fn with_location(mut self, file: &'static str, line: u32, column: u32) -> Self {
+        self.location = Some(Location { file, line, column });
+        self.location_is_caller = false;
+        self
+    }
+
+    #[doc(hidden)]
+    pub fn __location(mut self, module: &'static str, file: &'static str, line: u32, column: u32) -> Self {
+        self.module = Some(module);
+        self.with_location(file, line, column)
+    }
+
+    /// Get a reference to the `cause`
+    pub fn cause(&self) -> &T {
+        &self.cause
+    }
+
+    /// Get a mutable reference to the `cause`
+    pub fn cause_mut(&mut self) -> &mut T {
+        &mut self.cause
+    }
+
+    /// Wrap this error in an `Err`, e.g. `return cause!(ErrorType::NotFoundError).into_err();`.
+    ///
+    /// See also the [bail] macro.
+    ///
+    /// ```
+    /// use cause::{cause, Cause};
+    /// # #[derive(Debug)]
+    /// # enum ErrorType { NotFoundError }
+    ///
+    /// fn find(id: u32) -> Result<String, Cause<ErrorType>> {
+    ///     if id != 42 {
+    ///         return cause!(ErrorType::NotFoundError, "no user {}", id).into_err();
+    ///     }
+    ///     Ok("alice".to_string())
+    /// }
+    /// assert!(find(1).is_err());
+    /// ```
+    pub fn into_err<O>(self) -> Result<O, Self> {
+        Err(self)
+    }
+
+    /// Replace the `cause` of this error, keeping the other attributes.
+    pub fn set_cause(&mut self, cause: T) {
+        self.cause = cause;
+    }
+
+    /// Check if the `cause` of this error equals `kind`.
+    ///
+    /// ```
+    /// use cause::Cause;
+    /// # #[derive(Debug, PartialEq)]
+    /// # enum ErrorType { NotFoundError, InternalError }
+    ///
+    /// let cause = Cause::new(ErrorType::NotFoundError);
+    /// assert!(cause.is_kind(&ErrorType::NotFoundError));
+    /// assert!(!cause.is_kind(&ErrorType::InternalError));
+    /// ```
+    pub fn is_kind(&self, kind: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        self.cause() == kind
+    }
+
+    /// Check if the `cause` of this error satisfies the predicate `f`.
+    ///
+    /// ```
+    /// use cause::Cause;
+    /// # #[derive(Debug)]
+    /// # enum ErrorType { HttpError(u16) }
+    ///
+    /// let cause = Cause::new(ErrorType::HttpError(503));
+    /// assert!(cause.matches(|c| matches!(c, ErrorType::HttpError(500..=599))));
+    /// ```
+    pub fn matches<F: Fn(&T) -> bool>(&self, f: F) -> bool {
+        f(self.cause())
+    }
+
+    /// Consume this error and return its `cause`
+    pub fn into_inner(self) -> T {
+        self.cause
+    }
+
+    /// Transform the `cause` with `f`, keeping the message, source and location as they are.
+    pub fn map<U, F: FnOnce(T) -> U>(self, f: F) -> Cause<U, S> {
+        Cause {
+            cause: f(self.cause),
+            msg: self.msg,
+            src: self.src,
+            location: self.location,
+            location_is_caller: self.location_is_caller,
+            module: self.module,
+            #[cfg(feature = "std")]
+            backtrace: self.backtrace,
+            details: self.details,
+        }
+    }
+
+    /// Get a reference to the message
+    pub fn message(&self) -> Option<&str> {
+        self.msg.as_ref().map(Message::as_str)
+    }
+
+    /// Get a reference to the location where this error was created, if recorded
+    ///
+    /// It is recorded by the [cause] macro, or by [Cause::new] as the location of its caller.
+    pub fn location(&self) -> Option<&Location> {
+        self.location.as_ref()
+    }
+
+    // The message rendered by Display, truncated by `truncate_to`.
+    fn shown_message(&self) -> Option<Cow<'_, str>> {
+        let message = self.message()?;
+        match self.details.as_ref().and_then(|d| d.max_message_len) {
+            Some(n) => match message.char_indices().nth(n) {
+                Some((end, _)) => Some(Cow::Owned(format!("{}…", &message[..end]))),
+                None => Some(Cow::Borrowed(message)),
+            },
+            None => Some(Cow::Borrowed(message)),
+        }
+    }
+
+    // The location rendered by Display, which is not the one tracked by `Cause::new`.
+    fn shown_location(&self) -> Option<&Location> {
+        self.location.as_ref().filter(|_| !self.location_is_caller)
+    }
+
+    /// Get the module path where this error was created, if recorded
+    pub fn module(&self) -> Option<&'static str> {
+        self.module
+    }
+
+    /// Get the sub errors held by [Cause::aggregate]
+    pub fn sub_errors(&self) -> &[Box<S>] {
+        match &self.details {
+            Some(d) => &d.sub_errors,
+            None => &[],
+        }
+    }
+
+    /// Get the notes appended by [Cause::note]
+    pub fn notes(&self) -> &[String] {
+        match &self.details {
+            Some(d) => &d.notes,
+            None => &[],
+        }
+    }
+
+    /// Get the key-value fields attached by [Cause::field]
+    pub fn fields(&self) -> &[(&'static str, String)] {
+        match &self.details {
+            Some(d) => &d.fields,
+            None => &[],
+        }
+    }
+
+    /// Get the time recorded by [Cause::with_timestamp]
+    #[cfg(feature = "std")]
+    pub fn timestamp(&self) -> Option<SystemTime> {
+        self.details.as_ref()?.timestamp
+    }
+
+    /// Get the severity set by [Cause::with_severity]
+    pub fn severity(&self) -> Severity {
+        self.details.as_ref().and_then(|d| d.severity).unwrap_or_default()
+    }
+
+    /// Get the RFC 5424 syslog severity value of this error, mapped from [Cause::severity] by [Severity::syslog_severity].
+    ///
+    /// ```
+    /// use cause::{Cause, Severity};
+    /// # #[derive(Debug)]
+    /// # enum ErrorType { InternalError }
+    ///
+    /// assert_eq!(Cause::new(ErrorType::InternalError).syslog_severity(), 3);
+    /// assert_eq!(Cause::new(ErrorType::InternalError).with_severity(Severity::Fatal).syslog_severity(), 2);
+    /// ```
+    pub fn syslog_severity(&self) -> u8 {
+        self.severity().syslog_severity()
+    }
+
+    /// Get the numeric error code set by [Cause::with_code]
+    pub fn code(&self) -> Option<u32> {
+        self.details.as_ref()?.code
+    }
+
+    /// Get whether this error is set retryable by [Cause::retryable]
+    pub fn is_retryable(&self) -> bool {
+        self.details.as_ref().is_some_and(|d| d.retryable)
+    }
+
+    /// Get the help message set by [Cause::help]
+    pub fn help_text(&self) -> Option<&str> {
+        self.details.as_ref()?.help.as_deref()
+    }
+
+    /// Get a reference to the backtrace, if [Cause::with_backtrace] was called
+    #[cfg(feature = "std")]
+    pub fn backtrace(&self) -> Option<&Backtrace> {
+        self.backtrace.as_deref()
+    }
+
+    /// Check if this error has a source
+    pub fn has_source(&self) -> bool {
+        self.src.is_some()
+    }
+
+    /// Take the source out of this error, leaving `None` in its place.
+    ///
+    /// A source set by [Cause::src_shared] is returned as the `Arc<dyn Error + Send + Sync>` holding it,
+    /// and one set by [Cause::src_cloneable] as the error itself.
+    ///
+    /// ```
+    /// use cause::Cause;
+    /// use std::error::Error;
+    /// use std::sync::Arc;
+    /// # #[derive(Debug)]
+    /// # enum ErrorType { InternalError }
+    ///
+    /// let shared: Arc<dyn Error + Send + Sync> = Arc::new(std::fmt::Error);
+    /// let mut cause = Cause::new(ErrorType::InternalError).src_shared(shared.clone());
+    /// let src = cause.take_source().unwrap();
+    /// assert!(Arc::ptr_eq(src.downcast_ref::<Arc<dyn Error + Send + Sync>>().unwrap(), &shared));
+    /// ```
+    pub fn take_source(&mut self) -> Option<Box<S>> {
+        self.src.take().map(unwrap_source)
+    }
+
+    /// Remove the source of this error and return it, same as [Cause::take_source].
+    pub fn clear_source(&mut self) -> Option<Box<S>> {
+        self.take_source()
+    }
+
+    /// Remove the message of this error.
+    pub fn clear_message(&mut self) {
+        self.msg = None;
+    }
+
+    /// Consume this error and return its source, the same as [Cause::take_source] returns.
+    pub fn into_source(self) -> Option<Box<S>> {
+        self.src.map(unwrap_source)
+    }
+}
+
+use core::fmt::Display;
+use core::fmt::Debug;
+use core::hash::{Hash, Hasher};
+
+/// The alternate flag `{:#}` renders the whole source chain as a numbered list,
+/// and the minus flag `{:-}` renders only this error itself without its source.
+/// The plus flag `{:+}` drops the trailing newline after the source, e.g. for loggers adding their own.
+///
+/// The `cause` is rendered by the formatter set by [set_formatter] for its type, or by [Debug].
+///
+/// ```
+/// use cause::Cause;
+/// # #[derive(Debug)]
+/// # enum ErrorType { InternalError, NotFoundError }
+///
+/// let io_err = std::io::Error::other("oh no!");
+/// let cause = Cause::new(ErrorType::InternalError).msg("outer")
+///     .src(Cause::new(ErrorType::NotFoundError).msg("inner").src(io_err));
+/// assert_eq!(format!("{:#}", cause), "0: InternalError: outer\n1: NotFoundError: inner\n2: oh no!");
+/// assert_eq!(format!("{:-}", cause), "InternalError: outer");
+///
+/// let cause = Cause::new(ErrorType::InternalError).src(std::fmt::Error);
+/// assert_eq!(format!("{:+}", cause), "InternalError\n\nCaused by:\n    an error occurred when formatting an argument");
+/// ```
+impl<T: Debug + 'static, S: ?Sized + Source> Display for Cause<T, S> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> Result<(), core::fmt::Error> {
+        self.render(f, format_args!("{}", FormattedCause(&self.cause)))
+    }
+}
+
+// Renders a `cause` by the formatter set by `set_formatter`, falling back to `Debug`.
+struct FormattedCause<'a, T>(&'a T);
+
+impl<T: Debug + 'static> Display for FormattedCause<'_, T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> Result<(), core::fmt::Error> {
+        // The lock is released before the formatter is invoked, so that it can render another `Cause`.
+        #[cfg(feature = "std")]
+        if HAS_FORMATTERS.load(std::sync::atomic::Ordering::Acquire) {
+            let id = core::any::TypeId::of::<T>();
+            let formatter = FORMATTERS
+                .read()
+                .unwrap_or_else(|e| e.into_inner())
+                .iter()
+                .find(|(t, _)| *t == id)
+                .map(|(_, formatter)| formatter.clone());
+            if let Some(formatter) = formatter {
+                return formatter(self.0, f);
+            }
+        }
+        Debug::fmt(self.0, f)
+    }
+}
+
+/// [Debug] renders the attributes which are set, and the source chain as a list of the headlines of the sources,
+/// so that the panic message of `unwrap()` is readable.
+///
+/// ```
+/// use cause::Cause;
+/// # #[derive(Debug)]
+/// # enum ErrorType { InternalError }
+///
+/// let cause = Cause::new(ErrorType::InternalError).msg("oops").src(std::io::Error::other("oh no!"));
+/// let cause = cause.with_location("main.rs", 1, 2);
+/// assert_eq!(
+///     format!("{:?}", cause),
+///     r#"Cause { cause: InternalError, message: "oops", location: main.rs:1:2, sources: ["oh no!"] }"#,
+/// );
+/// ```
+impl<T: Debug, S: ?Sized + Source> Debug for Cause<T, S> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> Result<(), core::fmt::Error> {
+        let mut d = f.debug_struct("Cause");
+        d.field("cause", &self.cause);
+        if let Some(message) = self.message() {
+            d.field("message", &message);
+        }
+        if let Some(location) = self.location() {
+            d.field("location", &format_args!("{}", location));
+        }
+        if let Some(module) = self.module() {
+            d.field("module", &module);
+        }
+        if let Some(details) = &self.details {
+            if !details.sub_errors.is_empty() {
+                let sub_errors: Vec<String> = plain_locations(|| details.sub_errors.iter().map(|e| format!("{:-}", e)).collect());
+                d.field("sub_errors", &sub_errors);
+            }
+            if !details.notes.is_empty() {
+                d.field("notes", &details.notes);
+            }
+            if !details.fields.is_empty() {
+                d.field("fields", &details.fields);
+            }
+            if let Some(help) = &details.help {
+                d.field("help", help);
+            }
+            if let Some(severity) = &details.severity {
+                d.field("severity", severity);
+            }
+            if let Some(code) = &details.code {
+                d.field("code", code);
+            }
+        }
+        let sources: Vec<String> = plain_locations(|| Chain::new(self.source_ref()).map(|s| format!("{:-}", s)).collect());
+        if !sources.is_empty() {
+            d.field("sources", &sources);
+        }
+        d.finish()
+    }
+}
+
+impl<T, S: ?Sized + Source> Cause<T, S> {
+    fn source_ref(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.src {
+            Some(e) => {
+                let e = e.as_error();
+                if let Some(shared) = e.downcast_ref::<SharedError>() {
+                    Some(shared.0.as_ref())
+                } else if let Some(cloneable) = e.downcast_ref::<CloneableSource>() {
+                    Some(cloneable.0.as_error())
+                } else {
+                    Some(e)
+                }
+            }
+            None => None,
+        }
+    }
+
+    fn source_label(&self) -> &'static str {
+        self.details.as_ref().and_then(|d| d.source_label).unwrap_or("Caused by:")
+    }
+
+    fn render_details(&self) -> String {
+        let mut notes = String::new();
+        for (i, e) in self.sub_errors().iter().enumerate() {
+            if i == 0 {
+                notes.push_str("\n\nErrors:");
+            }
+            notes.push_str(&format!("\n    {}: {:-}", i, e));
+        }
+        for (i, note) in self.notes().iter().enumerate() {
+            if i == 0 {
+                notes.push_str("\n\nNotes:");
+            }
+            notes.push_str(&format!("\n    - {}", note));
+        }
+        for (i, (key, value)) in self.fields().iter().enumerate() {
+            if i == 0 {
+                notes.push_str("\n\nFields:");
+            }
+            notes.push_str(&format!("\n    {}={}", key, value));
+        }
+        if let Some(help) = self.help_text() {
+            notes.push_str(&format!("\n\nhelp: {}", help));
+        }
+        notes
+    }
+
+    fn render(&self, f: &mut core::fmt::Formatter, cause: core::fmt::Arguments) -> Result<(), core::fmt::Error> {
+        let mut message: String = match (self.shown_message(), self.shown_location().map(LocationLink)) {
+            (Some(m), Some(l)) => format!("{}: {} [{}]", cause, m, l),
+            (Some(m), None) => format!("{}: {}", cause, m),
+            (None, Some(l)) => format!("{}: [{}]", cause, l),
+            (None, None) => format!("{}", cause),
+        };
+        if let Some(code) = self.code() {
+            message.insert_str(0, &format!("[E{:04}] ", code));
+        }
+        if f.sign_minus() {
+            return write!(f, "{}", message);
+        }
+        if f.alternate() {
+            message.insert_str(0, "0: ");
+            let sources = Chain::new(self.source_ref());
+            for (i, s) in sources.enumerate() {
+                message.push_str(&format!("\n{}: {:-}", i + 1, s));
+            }
+            message.push_str(&self.render_details());
+        } else {
+            message.push_str(&self.render_details());
+            if let Some(ref s) = self.src {
+                if f.sign_plus() {
+                    message.push_str(&format!("\n\n{}\n    {:+}", self.source_label(), s));
+                } else {
+                    message.push_str(&format!("\n\n{}\n    {}\n", self.source_label(), s));
+                }
+            }
+        }
+        #[cfg(feature = "std")]
+        if let Some(b) = self.backtrace.as_ref().filter(|b| b.status() == BacktraceStatus::Captured) {
+            message.push_str(&format!("\n\nStack backtrace:\n{}", b));
+        }
+        write!(f, "{}", message)
+    }
+}
+
+impl<T: Debug + 'static, S: ?Sized + Source> Cause<T, S> {
+
+    /// Write this error and its whole source chain to `w`.
+    ///
+    /// ```
+    /// use cause::Cause;
+    /// # #[derive(Debug)]
+    /// # enum ErrorType { InternalError, NotFoundError }
+    ///
+    /// let io_err = std::io::Error::other("oh no!");
+    /// let cause = Cause::new(ErrorType::InternalError).src(Cause::new(ErrorType::NotFoundError).src(io_err));
+    /// let mut buf = Vec::new();
+    /// cause.report(&mut buf).unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(buf).unwrap(),
+    ///     "InternalError\n\nCaused by:\n    0: NotFoundError\n    1: oh no!\n"
+    /// );
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn report<W: std::io::Write>(&self, w: &mut W) -> std::io::Result<()> {
+        self.write_report(w, false)
+    }
+
+    /// Write this error and its whole source chain to `w`, followed by the module path, timestamp and backtrace if recorded.
+    #[cfg(feature = "std")]
+    pub fn report_verbose<W: std::io::Write>(&self, w: &mut W) -> std::io::Result<()> {
+        self.write_report(w, true)
+    }
+
+    /// Render only the `cause` and the message of this error, e.g. for a short notice in UI.
+    ///
+    /// Unlike `{:-}`, the location is not rendered either.
+    ///
+    /// ```
+    /// use cause::cause;
+    /// # #[derive(Debug)]
+    /// # enum ErrorType { InternalError }
+    ///
+    /// let cause = cause!(ErrorType::InternalError, "failed to save").src(std::io::Error::other("oh no!"));
+    /// assert_eq!(cause.summary(), "InternalError: failed to save");
+    /// ```
+    pub fn summary(&self) -> String {
+        match self.shown_message() {
+            Some(m) => format!("{}: {}", FormattedCause(&self.cause), m),
+            None => format!("{}", FormattedCause(&self.cause)),
+        }
+    }
+
+    /// Render this error and its whole source chain in a single line, joined by ` -> `.
+    ///
+    /// Line breaks in each error are escaped as `\n`.
+    ///
+    /// ```
+    /// use cause::Cause;
+    /// # #[derive(Debug)]
+    /// # enum ErrorType { InternalError, NotFoundError }
+    ///
+    /// let io_err = std::io::Error::other("oh\nno!");
+    /// let cause = Cause::new(ErrorType::InternalError).msg("oops").src(Cause::new(ErrorType::NotFoundError).src(io_err));
+    /// assert_eq!(cause.to_single_line(), "InternalError: oops -> NotFoundError -> oh\\nno!");
+    /// ```
+    pub fn to_single_line(&self) -> String {
+        let line = plain_locations(|| {
+            let mut line = format!("{:-}", self);
+            for s in Chain::new(self.source_ref()) {
+                line.push_str(" -> ");
+                line.push_str(&format!("{:-}", s));
+            }
+            line
+        });
+        line.replace('\r', "\\r").replace('\n', "\\n")
+    }
+
+    /// Render this error the same as [Display] with each line indented by `spaces` spaces,
+    /// e.g. to nest it inside other structured output. Empty lines are kept empty.
+    ///
+    /// ```
+    /// use cause::Cause;
+    /// # #[derive(Debug)]
+    /// # enum ErrorType { InternalError }
+    ///
+    /// let cause = Cause::new(ErrorType::InternalError).msg("oops").src(std::io::Error::other("oh no!"));
+    /// assert_eq!(cause.indent_chain(2), "  InternalError: oops\n\n  Caused by:\n      oh no!\n");
+    /// ```
+    pub fn indent_chain(&self, spaces: usize) -> String {
+        let indent = " ".repeat(spaces);
+        let rendered = self.to_string();
+        let mut indented = String::with_capacity(rendered.len());
+        for line in rendered.split_inclusive('\n') {
+            if line != "\n" {
+                indented.push_str(&indent);
+            }
+            indented.push_str(line);
+        }
+        indented
+    }
+
+    #[cfg(feature = "std")]
+    fn write_report<W: std::io::Write>(&self, w: &mut W, verbose: bool) -> std::io::Result<()> {
+        plain_locations(|| self.write_plain_report(w, verbose))
+    }
+
+    #[cfg(feature = "std")]
+    fn write_plain_report<W: std::io::Write>(&self, w: &mut W, verbose: bool) -> std::io::Result<()> {
+        write!(w, "{:-}{}", self, self.render_details())?;
+        let sources = Chain::new(self.source_ref());
+        for (i, s) in sources.enumerate() {
+            if i == 0 {
+                write!(w, "\n\n{}", self.source_label())?;
+            }
+            write!(w, "\n    {}: {:-}", i, s)?;
+        }
+        if verbose {
+            if let Some(module) = self.module {
+                write!(w, "\n\nModule:\n    {}", module)?;
+            }
+            if let Some(t) = self.timestamp().and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok()) {
+                write!(w, "\n\nTimestamp:\n    {}.{:03}", t.as_secs(), t.subsec_millis())?;
+            }
+            if let Some(b) = self.backtrace.as_ref().filter(|b| b.status() == BacktraceStatus::Captured) {
+                write!(w, "\n\nStack backtrace:\n{}", b)?;
+            }
+        }
+        writeln!(w)
+    }
+}
+
+#[cfg(feature = "json")]
+impl<T: Debug, S: ?Sized + Source> Cause<T, S> {
+
+    /// Render this error as a compact JSON string (requires `json` feature).
+    ///
+    /// The `cause` is rendered with its [Debug], and each source in the chain is rendered
+    /// as a nested object holding its string representation.
+    /// Fields attached by [Cause::field] are rendered as a `fields` object when there are any.
+    ///
+    /// ```
+    /// use cause::Cause;
+    /// # #[derive(Debug)]
+    /// # enum ErrorType { InternalError }
+    ///
+    /// let cause = Cause::new(ErrorType::InternalError).msg("oops").src(std::io::Error::other("oh no!"));
+    /// assert_eq!(
+    ///     cause.to_json_string(),
+    ///     r#"{"cause":"InternalError","message":"oops","location":null,"source":{"error":"oh no!","source":null}}"#
+    /// );
+    /// ```
+    pub fn to_json_string(&self) -> String {
+        plain_locations(|| self.render_json())
+    }
+
+    fn render_json(&self) -> String {
+        let mut json = String::from("{\"cause\":");
+        push_json_str(&mut json, &format!("{:?}", self.cause));
+        json.push_str(",\"message\":");
+        match self.message() {
+            Some(m) => push_json_str(&mut json, m),
+            None => json.push_str("null"),
+        }
+        json.push_str(",\"location\":");
+        match self.shown_location() {
+            Some(l) => push_json_str(&mut json, &l.to_string()),
+            None => json.push_str("null"),
+        }
+        for (i, (key, value)) in self.fields().iter().enumerate() {
+            json.push_str(if i == 0 { ",\"fields\":{" } else { "," });
+            push_json_str(&mut json, key);
+            json.push(':');
+            push_json_str(&mut json, value);
+        }
+        if !self.fields().is_empty() {
+            json.push('}');
+        }
+        json.push_str(",\"source\":");
+        let sources = Chain::new(self.source_ref());
+        let mut depth = 0;
+        for s in sources {
+            json.push_str("{\"error\":");
+            push_json_str(&mut json, &format!("{:-}", s));
+            json.push_str(",\"source\":");
+            depth += 1;
+        }
+        json.push_str("null");
+        json.push_str(&"}".repeat(depth + 1));
+        json
+    }
+
+    /// Render this error as an RFC 7807 `application/problem+json` object (requires `json` feature).
+    ///
+    /// The `cause` is rendered with its [Debug] as `title`, the message as `detail` and `status` as `status`,
+    /// and the sources in the chain are rendered as a `sources` extension member.
+    /// The members without a value are omitted.
+    ///
+    /// ```
+    /// use cause::Cause;
+    /// # #[derive(Debug)]
+    /// # enum ErrorType { NotFoundError }
+    ///
+    /// let cause = Cause::new(ErrorType::NotFoundError).msg("no such user").src(std::io::Error::other("oh no!"));
+    /// assert_eq!(
+    ///     cause.to_problem_json(Some(404)),
+    ///     r#"{"title":"NotFoundError","status":404,"detail":"no such user","sources":["oh no!"]}"#
+    /// );
+    /// assert_eq!(Cause::new(ErrorType::NotFoundError).to_problem_json(None), r#"{"title":"NotFoundError"}"#);
+    /// ```
+    pub fn to_problem_json(&self, status: Option<u16>) -> String {
+        plain_locations(|| self.render_problem_json(status))
+    }
+
+    fn render_problem_json(&self, status: Option<u16>) -> String {
+        let mut json = String::from("{\"title\":");
+        push_json_str(&mut json, &format!("{:?}", self.cause));
+        if let Some(status) = status {
+            json.push_str(&format!(",\"status\":{}", status));
+        }
+        if let Some(m) = self.message() {
+            json.push_str(",\"detail\":");
+            push_json_str(&mut json, m);
+        }
+        for (i, s) in Chain::new(self.source_ref()).enumerate() {
+            json.push_str(if i == 0 { ",\"sources\":[" } else { "," });
+            push_json_str(&mut json, &format!("{:-}", s));
+        }
+        if self.has_source() {
+            json.push(']');
+        }
+        json.push('}');
+        json
+    }
+}
+
+#[cfg(feature = "json")]
+fn push_json_str(json: &mut String, s: &str) {
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+}
+
+impl<T: Display, S: ?Sized + Source> Cause<T, S> {
+
+    /// Get an object to display this error with the [Display] of `cause` instead of its [Debug].
+    ///
+    /// The message and source are rendered the same as the [Display] of [Cause].
+    ///
+    /// ```
+    /// use cause::Cause;
+    /// # #[derive(Debug)]
+    /// # enum ErrorType { NotFoundError }
+    ///
+    /// impl std::fmt::Display for ErrorType {
+    ///     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    ///         write!(f, "User not found")
+    ///     }
+    /// }
+    ///
+    /// let cause = Cause::new(ErrorType::NotFoundError).msg("id 42");
+    /// assert_eq!(cause.to_string(), "NotFoundError: id 42");
+    /// assert_eq!(cause.display().to_string(), "User not found: id 42");
+    /// ```
+    pub fn display(&self) -> CauseDisplay<'_, T, S> {
+        CauseDisplay(self)
+    }
+}
+
+/// An object to display a [Cause] with the [Display] of its `cause`, created by [Cause::display].
+pub struct CauseDisplay<'a, T, S: ?Sized = dyn Error + Send + 'static>(&'a Cause<T, S>);
+
+impl<T: Display, S: ?Sized + Source> Display for CauseDisplay<'_, T, S> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> Result<(), core::fmt::Error> {
+        self.0.render(f, format_args!("{}", self.0.cause))
+    }
+}
+
+impl<T: Debug + 'static, S: ?Sized + Source> Error for Cause<T, S> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.source_ref()
+    }
+}
+
+impl<T: Debug + 'static, S: ?Sized + Source> Cause<T, S> {
+
+    /// Get this error as a `&dyn Error`, e.g. to pass it to iterator adapters without a cast.
+    ///
+    /// ```
+    /// use cause::Cause;
+    /// # #[derive(Debug)]
+    /// # enum ErrorType { InternalError, NotFoundError }
+    ///
+    /// let causes = [Cause::new(ErrorType::InternalError), Cause::new(ErrorType::NotFoundError)];
+    /// let messages: Vec<String> = causes.iter().map(Cause::as_dyn).map(ToString::to_string).collect();
+    /// assert_eq!(messages, ["InternalError", "NotFoundError"]);
+    /// ```
+    pub fn as_dyn(&self) -> &(dyn Error + 'static) {
+        self
+    }
+
+    /// Convert this error into a `Box<dyn Error>`, whatever its source is.
+    ///
+    /// See also [Cause::into_boxed].
+    pub fn into_dyn(self) -> Box<dyn Error + 'static> {
+        Box::new(self)
+    }
+
+    /// Get an iterator over this error followed by each of its nested sources.
+    ///
+    /// ```
+    /// use cause::Cause;
+    /// # #[derive(Debug)]
+    /// # enum ErrorType { InternalError, NotFoundError }
+    ///
+    /// let io_err = std::io::Error::other("oh no!");
+    /// let cause = Cause::new(ErrorType::InternalError).src(Cause::new(ErrorType::NotFoundError).src(io_err));
+    /// assert_eq!(cause.chain().count(), 3);
+    /// assert_eq!(cause.chain().last().unwrap().to_string(), "oh no!");
+    /// ```
+    pub fn chain(&self) -> Chain<'_> {
+        Chain::new(Some(self))
+    }
+
+    /// Get the deepest source of this error, or this error itself if it has no source.
+    ///
+    /// ```
+    /// use cause::Cause;
+    /// # #[derive(Debug)]
+    /// # enum ErrorType { InternalError, NotFoundError }
+    ///
+    /// let cause = Cause::new(ErrorType::InternalError).src(Cause::new(ErrorType::NotFoundError));
+    /// assert_eq!(cause.root_cause().to_string(), "NotFoundError");
+    /// ```
+    pub fn root_cause(&self) -> &(dyn Error + 'static) {
+        self.chain().last().unwrap_or(self)
+    }
+
+    /// Fold every error in [Cause::chain], starting with this error, into a single value.
+    ///
+    /// ```
+    /// use cause::Cause;
+    /// # #[derive(Debug)]
+    /// # enum ErrorType { InternalError, NotFoundError }
+    ///
+    /// let cause = Cause::new(ErrorType::InternalError).src(Cause::new(ErrorType::NotFoundError).src(std::fmt::Error));
+    /// let score = cause.fold_chain(0, |score, e| score + if e.is::<Cause<ErrorType>>() { 10 } else { 1 });
+    /// assert_eq!(score, 21);
+    /// ```
+    pub fn fold_chain<B, F: FnMut(B, &(dyn Error + 'static)) -> B>(&self, init: B, f: F) -> B {
+        self.chain().fold(init, f)
+    }
+
+    /// Get the number of sources nested under this error, `0` if it has no source.
+    ///
+    /// ```
+    /// use cause::Cause;
+    /// # #[derive(Debug)]
+    /// # enum ErrorType { InternalError, NotFoundError }
+    ///
+    /// assert_eq!(Cause::new(ErrorType::InternalError).depth(), 0);
+    /// let cause = Cause::new(ErrorType::InternalError).src(Cause::new(ErrorType::NotFoundError).src(std::fmt::Error));
+    /// assert_eq!(cause.depth(), 2);
+    /// ```
+    pub fn depth(&self) -> usize {
+        self.chain().count() - 1
+    }
+
+    /// Find the first source of type `E` in the source chain.
+    pub fn find<E: Error + 'static>(&self) -> Option<&E> {
+        self.chain().skip(1).find_map(|e| e.downcast_ref::<E>())
+    }
+
+    /// Find the `cause` of the first [Cause] of type `U` in the source chain,
+    /// which may be a [LocalCause] or a [SyncCause] as well.
+    ///
+    /// ```
+    /// use cause::Cause;
+    /// # #[derive(Debug)]
+    /// # enum ErrorType { InternalError }
+    /// # #[derive(Debug, PartialEq)]
+    /// # enum NetworkError { Timeout }
+    ///
+    /// let cause = Cause::new(ErrorType::InternalError).src(Cause::new(NetworkError::Timeout));
+    /// assert_eq!(cause.find_cause::<NetworkError>(), Some(&NetworkError::Timeout));
+    /// assert!(cause.find_cause::<ErrorType>().is_none());
+    /// ```
+    pub fn find_cause<U: Debug + 'static>(&self) -> Option<&U> {
+        self.chain().skip(1).find_map(|e| {
+            e.downcast_ref::<Cause<U>>()
+                .map(Cause::cause)
+                .or_else(|| e.downcast_ref::<LocalCause<U>>().map(Cause::cause))
+                .or_else(|| e.downcast_ref::<SyncCause<U>>().map(Cause::cause))
+        })
+    }
+
+    /// Get the immediate source of this error as a [Cause] of type `U`, if it is one.
+    ///
+    /// ```
+    /// use cause::Cause;
+    /// # #[derive(Debug)]
+    /// # enum ErrorType { InternalError }
+    /// # #[derive(Debug, PartialEq)]
+    /// # enum NetworkError { Timeout }
+    ///
+    /// let cause = Cause::new(ErrorType::InternalError).src(Cause::new(NetworkError::Timeout).msg("after 30s"));
+    /// assert_eq!(cause.source_as::<NetworkError>().unwrap().message(), Some("after 30s"));
+    /// ```
+    pub fn source_as<U: Debug + 'static>(&self) -> Option<&Cause<U>> {
+        self.source_ref()?.downcast_ref::<Cause<U>>()
+    }
+}
+
+/// The maximum number of errors walked through by [Chain].
+pub const MAX_CHAIN_DEPTH: usize = 128;
+
+/// An iterator over an error and its nested sources, created by [Cause::chain].
+///
+/// It stops at an error whose source is the error itself, so that such an error doesn't make it endless.
+/// Longer cycles are cut off after [MAX_CHAIN_DEPTH] errors.
+#[derive(Debug, Clone)]
+pub struct Chain<'a> {
+    next: Option<&'a (dyn Error + 'static)>,
+    remaining: usize,
+}
+
+impl<'a> Chain<'a> {
+    fn new(next: Option<&'a (dyn Error + 'static)>) -> Self {
+        Chain { next, remaining: MAX_CHAIN_DEPTH }
+    }
+}
+
+impl<'a> Iterator for Chain<'a> {
+    type Item = &'a (dyn Error + 'static);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let current = self.next?;
+        self.next = match current.source() {
+            Some(s) if core::ptr::addr_eq(s, current) => None,
+            source => source,
+        };
+        Some(current)
+    }
+}
+
+impl<T: Debug + Send + 'static> Cause<T> {
+
+    /// Wrap this error into a new [Cause] with `kind`, whose source is this error.
+    ///
+    /// ```
+    /// use cause::Cause;
+    /// use std::error::Error;
+    /// # #[derive(Debug, PartialEq)]
+    /// # enum Inner { NotFoundError }
+    /// # #[derive(Debug, PartialEq)]
+    /// # enum Outer { InternalError }
+    ///
+    /// let cause = Cause::new(Inner::NotFoundError).wrap(Outer::InternalError).msg("while saving");
+    /// assert_eq!(*cause, Outer::InternalError);
+    /// assert_eq!(cause.source().unwrap().to_string(), "NotFoundError");
+    /// ```
+    pub fn wrap<K>(self, kind: K) -> Cause<K> {
+        Cause::new(kind).src(self)
+    }
+
+    /// Convert this error into a `Box<dyn Error + Send>`.
+    ///
+    /// Use [Cause::src_sync] beforehand to get a `Box<dyn Error + Send + Sync>` instead.
+    pub fn into_boxed(self) -> Box<dyn Error + Send + 'static> {
+        Box::new(self)
+    }
+
+    /// Get the source of this error, the same as [Error::source] but keeping it `Send`.
+    ///
+    /// ```
+    /// use cause::Cause;
+    /// # #[derive(Debug)]
+    /// # enum ErrorType { InternalError }
+    ///
+    /// fn assert_send<E: Send + ?Sized>(_: &E) {}
+    ///
+    /// let cause = Cause::new(ErrorType::InternalError).src(std::io::Error::other("oh no!"));
+    /// let source = cause.source_send().unwrap();
+    /// assert_send(source);
+    /// assert_eq!(source.to_string(), "oh no!");
+    /// ```
+    pub fn source_send(&self) -> Option<&(dyn Error + Send + 'static)> {
+        let e = self.src.as_deref()?;
+        if let Some(shared) = e.downcast_ref::<SharedError>() {
+            Some(shared.0.as_ref())
+        } else if let Some(cloneable) = e.downcast_ref::<CloneableSource>() {
+            Some(cloneable.0.as_error())
+        } else {
+            Some(e)
+        }
+    }
+
+    /// Combine another independent error into this error, e.g. a cleanup failure while handling this error.
+    ///
+    /// The `cause` of this error is kept, the messages are joined by `"; "`, the notes of `other` are appended,
+    /// and `other` itself is appended to the sub errors.
+    ///
+    /// ```
+    /// use cause::Cause;
+    /// # #[derive(Debug)]
+    /// # enum ErrorType { InternalError, CleanupError }
+    ///
+    /// let cause = Cause::new(ErrorType::InternalError).msg("failed to save")
+    ///     .merge(Cause::new(ErrorType::CleanupError).msg("failed to remove the temporary file"));
+    /// assert_eq!(cause.message(), Some("failed to save; failed to remove the temporary file"));
+    /// assert_eq!(cause.sub_errors()[0].to_string(), "CleanupError: failed to remove the temporary file");
+    /// ```
+    pub fn merge(mut self, other: Cause<T>) -> Self {
+        self.msg = match (self.message(), other.message()) {
+            (Some(this), Some(other)) => Some(Message::Text(Cow::Owned(format!("{}; {}", this, other)))),
+            (None, Some(other)) => Some(Message::Text(Cow::Owned(other.to_string()))),
+            (_, None) => self.msg,
+        };
+        let notes = other.notes().to_vec();
+        let details = self.details_mut();
+        details.notes.extend(notes);
+        details.sub_errors.push(Box::new(other));
+        self
+    }
+}
+
+impl<T: PartialEq + Debug + Send + 'static> Cause<T> {
+
+    /// Collapse the sources which are [Cause]s with the same `cause` as this error into this error.
+    ///
+    /// Their messages are joined by `": "`, their notes, fields and sub errors are appended,
+    /// and the source of the last collapsed one becomes the source of this error.
+    /// The other attributes of this error are kept, and only missing ones are taken from the collapsed ones.
+    ///
+    /// ```
+    /// use cause::Cause;
+    /// # #[derive(Debug, PartialEq)]
+    /// # enum ErrorType { InternalError, NotFoundError }
+    ///
+    /// let cause = Cause::new(ErrorType::InternalError)
+    ///     .msg("while saving")
+    ///     .src(Cause::new(ErrorType::InternalError).msg("while writing").src(Cause::new(ErrorType::NotFoundError)))
+    ///     .flatten();
+    /// assert_eq!(cause.to_string(), "InternalError: while saving: while writing\n\nCaused by:\n    NotFoundError\n");
+    /// ```
+    pub fn flatten(mut self) -> Self {
+        while let Some(src) = self.src.take() {
+            let inner = match src.downcast::<Cause<T>>() {
+                Ok(inner) if inner.cause == self.cause => *inner,
+                Ok(inner) => {
+                    self.src = Some(inner);
+                    break;
+                }
+                Err(src) => {
+                    self.src = Some(src);
+                    break;
+                }
+            };
+            self.msg = match (self.message(), inner.message()) {
+                (Some(outer), Some(inner)) => Some(Message::Text(Cow::Owned(format!("{}: {}", outer, inner)))),
+                (None, Some(_)) => inner.msg,
+                (_, None) => self.msg,
+            };
+            self.src = inner.src;
+            #[cfg(feature = "std")]
+            if self.backtrace.is_none() {
+                self.backtrace = inner.backtrace;
+            }
+            if let Some(inner) = inner.details {
+                let details = self.details_mut();
+                details.sub_errors.extend(inner.sub_errors);
+                details.notes.extend(inner.notes);
+                details.fields.extend(inner.fields);
+                details.source_label = details.source_label.or(inner.source_label);
+                details.help = details.help.take().or(inner.help);
+                details.severity = details.severity.or(inner.severity);
+                details.code = details.code.or(inner.code);
+                details.retryable |= inner.retryable;
+                details.max_message_len = details.max_message_len.or(inner.max_message_len);
+                #[cfg(feature = "std")]
+                {
+                    details.timestamp = details.timestamp.or(inner.timestamp);
+                }
+            }
+        }
+        self
+    }
+}
+
+impl<T: Debug + Send + Sync + 'static> SyncCause<T> {
+
+    /// Convert this error into a `Box<dyn Error + Send + Sync>`.
+    ///
+    /// ```
+    /// use cause::Cause;
+    /// use std::error::Error;
+    /// # #[derive(Debug)]
+    /// # enum ErrorType { InternalError }
+    ///
+    /// fn run() -> Result<(), Box<dyn Error + Send + Sync>> {
+    ///     let io_err = std::io::Error::other("oh no!");
+    ///     Err(Cause::new(ErrorType::InternalError).src_sync(io_err).into_boxed())
+    /// }
+    /// assert!(run().is_err());
+    /// ```
+    pub fn into_boxed(self) -> Box<dyn Error + Send + Sync + 'static> {
+        Box::new(self)
+    }
+}
+
+/// A trait to map the `cause` of a [Cause] to the exit code of the process.
+///
+/// The default implementation returns `1`,
+/// so an empty `impl ExitCode for ErrorType {}` is enough for types who don't care.
+///
+/// ```
+/// use cause::ExitCode;
+/// # #[derive(Debug)]
+/// # enum ErrorType { InternalError, NotFoundError }
+///
+/// impl ExitCode for ErrorType {
+///     fn exit_code(&self) -> u8 {
+///         match self {
+///             ErrorType::InternalError => 1,
+///             ErrorType::NotFoundError => 4,
+///         }
+///     }
+/// }
+/// ```
+pub trait ExitCode {
+
+    /// Get the exit code of the process for this `cause`.
+    fn exit_code(&self) -> u8 {
+        1
+    }
+}
+
+/// A [Cause] can be returned from `main`, which prints it to the standard error
+/// and exits with the code given by [ExitCode].
+///
+/// ```no_run
+/// use cause::{Cause, ExitCode};
+/// # #[derive(Debug)]
+/// # enum ErrorType { InternalError }
+///
+/// impl ExitCode for ErrorType {}
+///
+/// fn main() -> Cause<ErrorType> {
+///     Cause::new(ErrorType::InternalError).msg("oh no!")
+/// }
+/// ```
+#[cfg(feature = "std")]
+impl<T: Debug + ExitCode + 'static, S: ?Sized + Source> std::process::Termination for Cause<T, S> {
+    fn report(self) -> std::process::ExitCode {
+        eprintln!("{}", self);
+        std::process::ExitCode::from(self.cause.exit_code())
+    }
+}
+
+/// A trait to map a `cause` to an HTTP status code.
+///
+/// ```
+/// use cause::{Cause, HttpStatus};
+/// # #[derive(Debug)]
+/// # enum ErrorType { InternalError, NotFoundError }
+///
+/// impl HttpStatus for ErrorType {
+///     fn status(&self) -> u16 {
+///         match self {
+///             ErrorType::InternalError => 500,
+///             ErrorType::NotFoundError => 404,
+///         }
+///     }
+/// }
+///
+/// assert_eq!(Cause::new(ErrorType::NotFoundError).status_code(), 404);
+/// ```
+pub trait HttpStatus {
+
+    /// Get the HTTP status code for this `cause`.
+    fn status(&self) -> u16;
+}
+
+impl<T: HttpStatus, S: ?Sized> Cause<T, S> {
+
+    /// Get the HTTP status code of the `cause`, given by [HttpStatus].
+    pub fn status_code(&self) -> u16 {
+        self.cause.status()
+    }
+}
+
+/// A trait to group `cause`s into coarse categories, e.g. for metrics tags.
+///
+/// ```
+/// use cause::{Cause, Category};
+/// # #[derive(Debug)]
+/// # enum ErrorType { InvalidArgumentsError, InternalError }
+///
+/// impl Category for ErrorType {
+///     fn category(&self) -> &'static str {
+///         match self {
+///             ErrorType::InvalidArgumentsError => "client",
+///             ErrorType::InternalError => "server",
+///         }
+///     }
+/// }
+///
+/// assert_eq!(Cause::new(ErrorType::InvalidArgumentsError).category(), "client");
+/// ```
+pub trait Category {
+
+    /// Get the category of this `cause`.
+    fn category(&self) -> &'static str;
+}
+
+impl<T: Category, S: ?Sized> Cause<T, S> {
+
+    /// Get the category of the `cause`, given by [Category].
+    pub fn category(&self) -> &'static str {
+        self.cause.category()
+    }
+}
+
+/// Cloning a [Cause] keeps its `cause`, message and location as they are,
+/// but the source is flattened into a [StringError] holding its string representation,
+/// because `dyn Error` can't be cloned, unless it was set by [Cause::src_shared] or [Cause::src_cloneable].
+/// The backtrace is not cloned either.
+impl<T: Clone, S: ?Sized + Source> Clone for Cause<T, S> {
+    fn clone(&self) -> Self {
+        Self {
+            cause: self.cause.clone(),
+            msg: self.message().map(|m| Message::Text(Cow::Owned(m.to_string()))),
+            src: self.src.as_ref().map(|s| S::from_sync(flatten_into_sync(s.as_error()))),
+            location: self.location,
+            location_is_caller: self.location_is_caller,
+            module: self.module,
+            #[cfg(feature = "std")]
+            backtrace: None,
+            details: self.details.clone(),
+        }
+    }
+}
+
+/// Two [Cause]s are equal when their `cause`, message, location and severity are equal.
+/// The location recorded by [Cause::new] as its caller is not compared.
+///
+/// Their sources are compared by their string representations, because `dyn Error` is not `PartialEq`.
+impl<T: PartialEq, S: ?Sized + Source> PartialEq for Cause<T, S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cause == other.cause
+            && self.message() == other.message()
+            && self.shown_location() == other.shown_location()
+            && self.module == other.module
+            && self.severity() == other.severity()
+            && self.src.as_ref().map(|s| s.to_string()) == other.src.as_ref().map(|s| s.to_string())
+    }
+}
+
+impl<T: Eq, S: ?Sized + Source> Eq for Cause<T, S> {}
+
+/// [Cause]s are ordered by their severity first, from the least severe to the most severe,
+/// so that the most severe one of a batch is the `max()` of them.
+///
+/// The ties are broken by their `cause`, message, location, module path and the string representation of their sources,
+/// to be consistent with [PartialEq]. The rest of the source chain is ignored.
+///
+/// ```
+/// use cause::{Cause, Severity};
+/// # #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+/// # enum ErrorType { InternalError, NotFoundError }
+///
+/// let mut errors = vec![
+///     Cause::new(ErrorType::NotFoundError).with_severity(Severity::Warning),
+///     Cause::new(ErrorType::InternalError).with_severity(Severity::Fatal),
+///     Cause::new(ErrorType::InternalError),
+/// ];
+/// errors.sort();
+/// assert_eq!(errors.iter().map(Cause::severity).collect::<Vec<_>>(), [Severity::Warning, Severity::Error, Severity::Fatal]);
+/// assert_eq!(errors.iter().max().unwrap().severity(), Severity::Fatal);
+/// ```
+impl<T: Ord, S: ?Sized + Source> Ord for Cause<T, S> {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        let location = |c: &Self| c.shown_location().map(|l| (l.file, l.line, l.column));
+        self.severity()
+            .cmp(&other.severity())
+            .then_with(|| self.cause.cmp(&other.cause))
+            .then_with(|| self.message().cmp(&other.message()))
+            .then_with(|| location(self).cmp(&location(other)))
+            .then_with(|| self.module.cmp(&other.module))
+            .then_with(|| self.src.as_ref().map(|s| s.to_string()).cmp(&other.src.as_ref().map(|s| s.to_string())))
+    }
+}
+
+impl<T: Ord, S: ?Sized + Source> PartialOrd for Cause<T, S> {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// A [Cause] is hashed consistently with [PartialEq],
+/// so its source is hashed by its string representation, not structurally.
+///
+/// `clippy::mutable_key_type` may warn about [Cause] keys, because a message set by [Cause::msg_with]
+/// is built lazily with interior mutability, but it never changes once built and is safe to hash.
+///
+/// ```
+/// use cause::Cause;
+/// use std::collections::HashSet;
+/// # #[derive(Debug, PartialEq, Eq, Hash)]
+/// # enum ErrorType { InternalError }
+///
+/// let causes: HashSet<_> = (0..3).map(|_| Cause::new(ErrorType::InternalError).msg("oops")).collect();
+/// assert_eq!(causes.len(), 1);
+/// ```
+impl<T: Hash, S: ?Sized + Source> Hash for Cause<T, S> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.cause.hash(state);
+        self.message().hash(state);
+        self.severity().hash(state);
+        self.shown_location().hash(state);
+        self.module.hash(state);
+        self.src.as_ref().map(|s| s.to_string()).hash(state);
+    }
+}
+
+#[cfg(feature = "std")]
+static OCCURRENCES: Mutex<alloc::collections::BTreeMap<u64, u64>> = Mutex::new(alloc::collections::BTreeMap::new());
+
+#[cfg(feature = "std")]
+impl<T: Hash, S: ?Sized + Source> Cause<T, S> {
+    fn occurrence_key(&self) -> u64 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Count this error in the global registry of occurrences, and return how many times
+    /// the errors identical to it by [Hash] have been counted, e.g. to suppress repeated logs.
+    ///
+    /// Nothing is counted unless this is called, and [clear_occurrences] resets the counts for a new window.
+    ///
+    /// The errors are keyed by a 64-bit digest of their [Hash], so two different errors which collide
+    /// share one count. The registry keeps one entry per distinct error and never evicts any,
+    /// so it grows until [clear_occurrences] is called; call it periodically when the messages are unbounded,
+    /// e.g. when they embed request IDs.
+    ///
+    /// ```
+    /// use cause::Cause;
+    /// # #[derive(Debug, PartialEq, Eq, Hash)]
+    /// # enum ErrorType { TimeoutError }
+    ///
+    /// for i in 1..=3 {
+    ///     let cause = Cause::new(ErrorType::TimeoutError).msg("upstream timed out");
+    ///     assert_eq!(cause.record_occurrence(), i);
+    /// }
+    /// assert_eq!(Cause::new(ErrorType::TimeoutError).msg("upstream timed out").occurrence_count(), 3);
+    /// assert_eq!(Cause::new(ErrorType::TimeoutError).occurrence_count(), 0);
+    ///
+    /// cause::clear_occurrences();
+    /// assert_eq!(Cause::new(ErrorType::TimeoutError).msg("upstream timed out").occurrence_count(), 0);
+    /// ```
+    pub fn record_occurrence(&self) -> u64 {
+        let key = self.occurrence_key();
+        let mut occurrences = OCCURRENCES.lock().unwrap_or_else(|e| e.into_inner());
+        let count = occurrences.entry(key).or_insert(0);
+        *count += 1;
+        *count
+    }
+
+    /// Get how many times the errors identical to this error have been counted by [Cause::record_occurrence].
+    pub fn occurrence_count(&self) -> u64 {
+        let key = self.occurrence_key();
+        let occurrences = OCCURRENCES.lock().unwrap_or_else(|e| e.into_inner());
+        occurrences.get(&key).copied().unwrap_or(0)
+    }
+}
+
+/// Reset the counts of [Cause::record_occurrence].
+#[cfg(feature = "std")]
+pub fn clear_occurrences() {
+    OCCURRENCES.lock().unwrap_or_else(|e| e.into_inner()).clear();
+}
+
+/// An error which only holds a string representation of another error.
+///
+/// It stands in for an original error which can't be kept as it is, e.g. the source of a cloned [Cause].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StringError(String);
+
+impl StringError {
+
+    /// Create a [StringError] instance with its string representation.
+    pub fn new(msg: impl Into<String>) -> Self {
+        Self(msg.into())
+    }
+}
+
+impl Display for StringError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> Result<(), core::fmt::Error> {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Error for StringError {}
+
+/// The message of a [Cause], which is built on demand if it was set by [Cause::msg_with].
+enum Message {
+    Text(Cow<'static, str>),
+    #[cfg(feature = "std")]
+    Lazy(Box<LazyMessage>),
+}
+
+#[cfg(feature = "std")]
+struct LazyMessage {
+    init: Mutex<Option<Box<dyn FnOnce() -> String + Send + 'static>>>,
+    value: OnceLock<String>,
+}
+
+impl Message {
+    fn as_str(&self) -> &str {
+        match self {
+            Message::Text(text) => text,
+            #[cfg(feature = "std")]
+            Message::Lazy(lazy) => lazy.value.get_or_init(|| {
+                let init = lazy.init.lock().unwrap_or_else(|e| e.into_inner()).take();
+                init.map(|f| f()).unwrap_or_default()
+            }),
+        }
+    }
+}
+
+impl Debug for Message {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> Result<(), core::fmt::Error> {
+        Debug::fmt(self.as_str(), f)
+    }
+}
+
+/// A source set by [Cause::src_shared], which behaves as the shared error itself.
+struct SharedError(Arc<dyn Error + Send + Sync + 'static>);
+
+impl Debug for SharedError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> Result<(), core::fmt::Error> {
+        Debug::fmt(&self.0, f)
+    }
+}
+
+impl Display for SharedError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> Result<(), core::fmt::Error> {
+        Display::fmt(&self.0, f)
+    }
+}
+
+impl Error for SharedError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.0.source()
+    }
+}
+
+/// An error which can be cloned behind a `dyn CloneableError`, used by [Cause::src_cloneable].
+///
+/// It is implemented for every error which is `Clone`, `Send` and `Sync`.
+pub trait CloneableError: Error + Send + Sync + 'static {
+
+    /// Clone this error into a new box.
+    fn clone_boxed(&self) -> Box<dyn CloneableError>;
+
+    /// Get this error as a `dyn Error`.
+    fn as_error(&self) -> &(dyn Error + Send + Sync + 'static);
+
+    /// Convert this boxed error into a boxed `dyn Error`.
+    fn into_error(self: Box<Self>) -> Box<dyn Error + Send + Sync + 'static>;
+}
+
+impl<E: Error + Clone + Send + Sync + 'static> CloneableError for E {
+    fn clone_boxed(&self) -> Box<dyn CloneableError> {
+        Box::new(self.clone())
+    }
+
+    fn as_error(&self) -> &(dyn Error + Send + Sync + 'static) {
+        self
+    }
+
+    fn into_error(self: Box<Self>) -> Box<dyn Error + Send + Sync + 'static> {
+        self
+    }
+}
+
+/// A source set by [Cause::src_cloneable], which behaves as the cloneable error itself.
+struct CloneableSource(Box<dyn CloneableError>);
+
+impl Debug for CloneableSource {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> Result<(), core::fmt::Error> {
+        Debug::fmt(self.0.as_error(), f)
+    }
+}
+
+impl Display for CloneableSource {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> Result<(), core::fmt::Error> {
+        Display::fmt(self.0.as_error(), f)
+    }
+}
+
+impl Error for CloneableSource {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.0.source()
+    }
+}
+
+/// A `cause` can be converted into a [Cause] directly, so that `?` lifts `Result<_, T>` into `Result<_, Cause<T>>`.
+///
+/// Because of this, [Cause] doesn't provide the other generic `From` implementations,
+/// e.g. from any `E: Error`, which would conflict with it.
+///
+/// ```
+/// use cause::Cause;
+/// # #[derive(Debug, PartialEq)]
+/// # enum ErrorType { NotFoundError }
+///
+/// fn lookup() -> Result<(), ErrorType> {
+///     Err(ErrorType::NotFoundError)
+/// }
+///
+/// fn run() -> Result<(), Cause<ErrorType>> {
+///     lookup()?;
+///     Ok(())
+/// }
+/// assert_eq!(*run().unwrap_err(), ErrorType::NotFoundError);
+/// ```
+impl<T> From<T> for Cause<T> {
+    #[cfg_attr(not(feature = "no-location"), track_caller)]
+    fn from(cause: T) -> Self {
+        Cause::new(cause)
+    }
+}
+
+/// A trait to designate the `cause` for foreign errors converted into a [Cause].
+///
+/// A generic `From<E: Error>` implementation would conflict with `From<T>`,
+/// so the conversion is done by [Cause::from_error] or [ResultExt::default_cause] instead.
+///
+/// ```
+/// use cause::{Cause, DefaultKind, ResultExt};
+/// # #[derive(Debug, PartialEq)]
+/// # enum ErrorType { NotFoundError, Fallback }
+///
+/// impl DefaultKind for ErrorType {
+///     fn default_kind() -> Self {
+///         ErrorType::Fallback
+///     }
+/// }
+///
+/// fn read() -> Result<String, Cause<ErrorType>> {
+///     let contents = std::fs::read_to_string("/no/such/file").default_cause()?;
+///     Ok(contents)
+/// }
+/// assert_eq!(*read().unwrap_err(), ErrorType::Fallback);
+/// ```
+pub trait DefaultKind {
+
+    /// Get the `cause` for foreign errors.
+    fn default_kind() -> Self;
+}
+
+impl<T: DefaultKind> Cause<T> {
+
+    /// Create a [Cause] instance with [DefaultKind::default_kind], whose source is `src`.
+    #[cfg_attr(not(feature = "no-location"), track_caller)]
+    pub fn from_error(src: impl Error + Send + 'static) -> Self {
+        Cause::new(T::default_kind()).src(src)
+    }
+}
+
+/// A trait to designate the `cause` for [std::io::Error]s, by their [std::io::ErrorKind].
+///
+/// Unlike the other foreign errors, an [std::io::Error] is converted by `From`,
+/// so that `?` lifts `Result<_, std::io::Error>` into `Result<_, Cause<T>>`.
+/// The [std::io::Error] becomes the source of the [Cause].
+///
+/// ```
+/// use cause::{Cause, FromIoError};
+/// use std::error::Error;
+/// use std::io::ErrorKind;
+/// # #[derive(Debug, PartialEq)]
+/// # enum ErrorType { NotFoundError, InternalError }
+///
+/// impl FromIoError for ErrorType {
+///     fn from_io_kind(kind: ErrorKind) -> Self {
+///         match kind {
+///             ErrorKind::NotFound => ErrorType::NotFoundError,
+///             _ => ErrorType::InternalError,
+///         }
+///     }
+/// }
+///
+/// fn read() -> Result<Vec<u8>, Cause<ErrorType>> {
+///     let contents = std::fs::read("/no/such/file")?;
+///     Ok(contents)
+/// }
+/// let cause = read().unwrap_err();
+/// assert_eq!(*cause, ErrorType::NotFoundError);
+/// assert!(cause.source().unwrap().is::<std::io::Error>());
+/// ```
+#[cfg(feature = "std")]
+pub trait FromIoError {
+
+    /// Get the `cause` for an [std::io::Error] of `kind`.
+    fn from_io_kind(kind: std::io::ErrorKind) -> Self;
+}
+
+#[cfg(feature = "std")]
+impl<T: FromIoError> From<std::io::Error> for Cause<T> {
+    #[cfg_attr(not(feature = "no-location"), track_caller)]
+    fn from(src: std::io::Error) -> Self {
+        Cause::new(T::from_io_kind(src.kind())).src(src)
+    }
+}
+
+/// A [Cause] is converted into an [std::io::Error] of [std::io::ErrorKind::Other] holding it,
+/// e.g. to return it from an implementation of [std::io::Read].
+///
+/// Since [std::io::Error] has to be `Sync`, it holds a [SyncCause], whose source is flattened
+/// the same as the one of a cloned [Cause] unless the source was `Sync` already.
+///
+/// ```
+/// use cause::{Cause, SyncCause};
+/// # #[derive(Debug)]
+/// # enum ErrorType { InternalError }
+///
+/// let io_err = std::io::Error::from(Cause::new(ErrorType::InternalError).msg("oops"));
+/// assert_eq!(io_err.kind(), std::io::ErrorKind::Other);
+/// assert!(io_err.get_ref().unwrap().is::<SyncCause<ErrorType>>());
+/// ```
+#[cfg(feature = "std")]
+impl<T: Debug + Send + Sync + 'static, S: ?Sized + Source> From<Cause<T, S>> for std::io::Error {
+    fn from(cause: Cause<T, S>) -> Self {
+        std::io::Error::other(cause.into_sync_cause())
+    }
+}
+
+use core::ops::Deref;
+
+impl<T: Debug, S: ?Sized> Deref for Cause<T, S> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.cause
+    }
+}
+
+/// [Cause] is not `Borrow<T>`, because it is compared and hashed by more than its `cause`.
+///
+/// ```
+/// use cause::Cause;
+/// # #[derive(Debug, PartialEq)]
+/// # enum ErrorType { NotFoundError }
+///
+/// fn is_not_found(cause: impl AsRef<ErrorType>) -> bool {
+///     *cause.as_ref() == ErrorType::NotFoundError
+/// }
+/// assert!(is_not_found(Cause::new(ErrorType::NotFoundError)));
+/// ```
+impl<T, S: ?Sized> AsRef<T> for Cause<T, S> {
+    fn as_ref(&self) -> &T {
+        &self.cause
+    }
+}
+
+impl<T, S: ?Sized> AsMut<T> for Cause<T, S> {
+    fn as_mut(&mut self) -> &mut T {
+        &mut self.cause
+    }
+}
+
+/// An extension trait to convert the error of a [Result] into the source of a [Cause].
+///
+/// ```
+/// use cause::{Cause, ResultExt};
+/// use std::error::Error;
+/// # #[derive(Debug)]
+/// # enum ErrorType { InternalError }
+///
+/// fn read() -> Result<String, Cause<ErrorType>> {
+///     let contents = std::fs::read_to_string("/no/such/file").cause(ErrorType::InternalError)?;
+///     Ok(contents)
+/// }
+/// assert!(read().unwrap_err().source().is_some());
+/// ```
+pub trait ResultExt<T> {
+
+    /// The error type of the [Result].
+    type Error;
+
+    /// Convert the error into a [Cause] with `kind`, whose source is the original error.
+    fn cause<K>(self, kind: K) -> Result<T, Cause<K>>;
 
-/// A tiny generic implementation of the [std::error::Error] trait.
-#[derive(Debug)]
-pub struct Cause<T> {
-    cause: T,
-    msg: Option<String>,
-    src: Option<Box<dyn Error + Send + 'static>>,
+    /// Same as [ResultExt::cause], named after [Result::map_err].
+    fn map_err_cause<K>(self, kind: K) -> Result<T, Cause<K>>;
+
+    /// Convert the error into a [Cause] whose `kind` is computed from the original error,
+    /// which becomes the source.
+    ///
+    /// ```
+    /// use cause::{Cause, ResultExt};
+    /// use std::io::ErrorKind;
+    /// # #[derive(Debug, PartialEq)]
+    /// # enum ErrorType { NotFoundError, InternalError }
+    ///
+    /// let cause = std::fs::read("/no/such/file")
+    ///     .map_err_cause_with(|e| match e.kind() {
+    ///         ErrorKind::NotFound => ErrorType::NotFoundError,
+    ///         _ => ErrorType::InternalError,
+    ///     })
+    ///     .unwrap_err();
+    /// assert_eq!(*cause, ErrorType::NotFoundError);
+    /// ```
+    fn map_err_cause_with<K>(self, f: impl FnOnce(&Self::Error) -> K) -> Result<T, Cause<K>>;
+
+    /// Convert the error into a [Cause] with `kind` and the message, whose source is the original error.
+    fn cause_msg<K>(self, kind: K, msg: impl Into<String>) -> Result<T, Cause<K>>;
+
+    /// Convert the error into a [Cause] with [DefaultKind::default_kind], whose source is the original error.
+    fn default_cause<K: DefaultKind>(self) -> Result<T, Cause<K>>;
 }
 
-impl<T> Cause<T> {
+impl<T, E: Error + Send + 'static> ResultExt<T> for Result<T, E> {
+    type Error = E;
 
-    /// Create a [Cause] instance with its `cause`.
-    pub fn new(cause: T) -> Self {
-        Self {
-            cause,
-            msg: None,
-            src: None,
+    #[cfg_attr(not(feature = "no-location"), track_caller)]
+    fn cause<K>(self, kind: K) -> Result<T, Cause<K>> {
+        match self {
+            Ok(v) => Ok(v),
+            Err(e) => Err(Cause::new(kind).src(e)),
         }
     }
 
-    /// Set the message for this error.
-    pub fn msg(mut self, msg: impl Into<String>) -> Self {
-        self.msg = Some(msg.into());
-        self
+    #[cfg_attr(not(feature = "no-location"), track_caller)]
+    fn map_err_cause<K>(self, kind: K) -> Result<T, Cause<K>> {
+        self.cause(kind)
     }
 
-    /// Set the lower-level source of this error, if any.
-    pub fn src(mut self, src: impl Error + Send + 'static) -> Self {
-        self.src = Some(Box::new(src));
-        self
+    #[cfg_attr(not(feature = "no-location"), track_caller)]
+    fn map_err_cause_with<K>(self, f: impl FnOnce(&E) -> K) -> Result<T, Cause<K>> {
+        match self {
+            Ok(v) => Ok(v),
+            Err(e) => Err(Cause::new(f(&e)).src(e)),
+        }
     }
 
-    /// Get a reference to the `cause`
-    pub fn cause(&self) -> &T {
-        &self.cause
+    #[cfg_attr(not(feature = "no-location"), track_caller)]
+    fn cause_msg<K>(self, kind: K, msg: impl Into<String>) -> Result<T, Cause<K>> {
+        match self {
+            Ok(v) => Ok(v),
+            Err(e) => Err(Cause::new(kind).msg(msg).src(e)),
+        }
     }
 
-    /// Get a reference to the message
-    pub fn message(&self) -> Option<&String> {
-        match self.msg.as_ref() {
-            Some(msg) => Some(msg),
-            None => None,
+    #[cfg_attr(not(feature = "no-location"), track_caller)]
+    fn default_cause<K: DefaultKind>(self) -> Result<T, Cause<K>> {
+        match self {
+            Ok(v) => Ok(v),
+            Err(e) => Err(Cause::from_error(e)),
         }
     }
 }
 
-use std::fmt::Display;
-use std::fmt::Debug;
+/// An extension trait to convert an [Option] into a [Result] with a [Cause].
+///
+/// ```
+/// use cause::{Cause, OptionExt};
+/// # #[derive(Debug, PartialEq)]
+/// # enum ErrorType { NotFoundError }
+///
+/// fn lookup(id: u32) -> Result<&'static str, Cause<ErrorType>> {
+///     let name = [(42, "alice")].iter().find(|(i, _)| *i == id).map(|(_, n)| *n);
+///     name.ok_or_cause_msg(ErrorType::NotFoundError, format!("no such user {}", id))
+/// }
+/// assert_eq!(lookup(42).unwrap(), "alice");
+/// assert_eq!(*lookup(7).unwrap_err(), ErrorType::NotFoundError);
+/// ```
+pub trait OptionExt<T> {
 
-impl<T: Debug> Display for Cause<T> {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
-        let mut message: String = match self.msg.as_ref() {
-            Some(m) => format!("{:?}: {}", self.cause, m),
-            None => format!("{:?}", self.cause),
-        };
-        if let Some(ref s) = self.src {
-            message.push_str(&format!("\n\nCaused by:\n    {}\n", s));
-        }
-        write!(f, "{}", message)
-    }
+    /// Convert `None` into a [Cause] with `kind`.
+    fn ok_or_cause<K>(self, kind: K) -> Result<T, Cause<K>>;
+
+    /// Convert `None` into a [Cause] with `kind` and the message.
+    fn ok_or_cause_msg<K>(self, kind: K, msg: impl Into<String>) -> Result<T, Cause<K>>;
 }
 
-impl<T: Debug> Error for Cause<T> {
-    fn source(&self) -> Option<&(dyn Error + 'static)> {
-        match &self.src {
-            Some(e) => Some(e.as_ref()),
-            None => None,
+impl<T> OptionExt<T> for Option<T> {
+    #[cfg_attr(not(feature = "no-location"), track_caller)]
+    fn ok_or_cause<K>(self, kind: K) -> Result<T, Cause<K>> {
+        match self {
+            Some(v) => Ok(v),
+            None => Err(Cause::new(kind)),
         }
     }
-}
 
-use std::ops::Deref;
-
-impl<T: Debug> Deref for Cause<T> {
-    type Target = T;
-    fn deref(&self) -> &T {
-        &self.cause
+    #[cfg_attr(not(feature = "no-location"), track_caller)]
+    fn ok_or_cause_msg<K>(self, kind: K, msg: impl Into<String>) -> Result<T, Cause<K>> {
+        match self {
+            Some(v) => Ok(v),
+            None => Err(Cause::new(kind).msg(msg)),
+        }
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
 
-    #[derive(Debug, PartialEq, Eq)]
+    #[allow(clippy::enum_variant_names)]
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
     pub enum ErrorType {
         InvalidArgumentsError,
         InternalError,
         UnknownError,
     }
 
-    #[derive(Debug)]
+    #[allow(clippy::enum_variant_names)]
+    #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
     enum AlphabetError {
         AError,
         BError,
@@ -214,8 +3210,641 @@ mod tests {
         println!("{}", Cause::new(InternalError).src(Cause::new(UnknownError).msg("nested")).msg("something went wrong"));
         println!("{}", Cause::new(InternalError).src(Cause::new(CError)).msg( "another nested"));
 
-        use std::io::{Error, ErrorKind};
-        let io_err = Error::new(ErrorKind::Other, "oh no!");
+        use std::io::Error;
+        let io_err = Error::other("oh no!");
         println!("{}", Cause::new(InternalError).src(io_err).msg("internal error caused by io error"));
     }
+
+    #[test]
+    fn clone_flattens_source() {
+        use ErrorType::*;
+        use super::{Cause, StringError};
+        use std::error::Error;
+
+        let cause = Cause::new(InternalError)
+            .msg("something went wrong")
+            .src(Cause::new(UnknownError).src(std::io::Error::other("oh no!")));
+        let cloned = cause.clone();
+        assert_eq!(*cloned, InternalError);
+        assert_eq!(cloned.message(), cause.message());
+        assert_eq!(cloned.to_string(), cause.to_string());
+
+        let source = cloned.source().unwrap();
+        assert!(source.downcast_ref::<StringError>().is_some());
+        assert!(source.source().is_none());
+    }
+
+    #[test]
+    fn partial_eq_works() {
+        use ErrorType::*;
+        use super::{Cause, StringError};
+
+        assert_eq!(Cause::new(InternalError).msg("x"), Cause::new(InternalError).msg("x"));
+        assert_ne!(Cause::new(InternalError).msg("x"), Cause::new(InternalError).msg("y"));
+        assert_ne!(Cause::new(InternalError), Cause::new(UnknownError));
+        assert_eq!(
+            Cause::new(InternalError).src(std::io::Error::other("oh no!")),
+            Cause::new(InternalError).src(StringError::new("oh no!")),
+        );
+    }
+
+    #[test]
+    #[allow(clippy::mutable_key_type)]
+    fn hash_agrees_with_eq() {
+        use ErrorType::*;
+        use super::{Cause, StringError};
+        use std::collections::HashSet;
+
+        let mut set = HashSet::new();
+        assert!(set.insert(Cause::new(InternalError).src(std::io::Error::other("oh no!"))));
+        assert!(!set.insert(Cause::new(InternalError).src(StringError::new("oh no!"))));
+        assert!(set.insert(Cause::new(InternalError).src(StringError::new("oh yes!"))));
+        assert!(set.insert(Cause::new(InternalError).msg("x")));
+        assert_eq!(set.len(), 3);
+    }
+
+    #[test]
+    fn find_cause_works() {
+        use ErrorType::*;
+        use AlphabetError::*;
+        use super::{Cause, StringError};
+
+        let cause = Cause::new(InternalError)
+            .src(Cause::new(UnknownError).src_sync(Cause::new(AError).src_sync(StringError::new("inner"))));
+        assert_eq!(cause.find_cause::<ErrorType>(), Some(&UnknownError));
+        assert!(matches!(cause.find_cause::<AlphabetError>(), Some(AError)));
+        assert!(cause.find_cause::<String>().is_none());
+    }
+
+    #[test]
+    fn ord_is_consistent_with_eq() {
+        use AlphabetError::*;
+        use super::{Cause, Severity};
+
+        let warning = Cause::new(BError).with_severity(Severity::Warning);
+        let error = Cause::new(AError);
+        assert!(warning < error);
+        assert_ne!(Cause::new(AError).with_severity(Severity::Warning), Cause::new(AError));
+        assert_eq!(Cause::new(AError).msg("x").cmp(&Cause::new(AError).msg("x")), std::cmp::Ordering::Equal);
+        assert!(Cause::new(AError).msg("x") < Cause::new(AError).msg("y"));
+        assert!(Cause::new(AError) < Cause::new(BError));
+    }
+
+    #[test]
+    fn root_cause_stops_at_self_source() {
+        use ErrorType::*;
+        use super::Cause;
+        use std::error::Error;
+        use std::fmt;
+
+        #[derive(Debug)]
+        struct Cyclic;
+        impl fmt::Display for Cyclic {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "cyclic")
+            }
+        }
+        impl Error for Cyclic {
+            fn source(&self) -> Option<&(dyn Error + 'static)> {
+                Some(self)
+            }
+        }
+
+        let cause = Cause::new(InternalError).src(Cyclic);
+        assert_eq!(cause.chain().count(), 2);
+        assert_eq!(cause.root_cause().to_string(), "cyclic");
+        assert_eq!(Cause::new(InternalError).root_cause().to_string(), "InternalError");
+    }
+
+    #[test]
+    fn chain_is_cut_off_at_max_depth() {
+        use ErrorType::*;
+        use super::{Cause, MAX_CHAIN_DEPTH};
+        use std::error::Error;
+        use std::fmt;
+
+        #[derive(Debug)]
+        struct Ping(u8);
+        #[derive(Debug)]
+        struct Pong(u8);
+        static PING: Ping = Ping(0);
+        static PONG: Pong = Pong(0);
+        impl fmt::Display for Ping {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "ping {}", self.0)
+            }
+        }
+        impl fmt::Display for Pong {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "pong {}", self.0)
+            }
+        }
+        impl Error for Ping {
+            fn source(&self) -> Option<&(dyn Error + 'static)> {
+                Some(&PONG)
+            }
+        }
+        impl Error for Pong {
+            fn source(&self) -> Option<&(dyn Error + 'static)> {
+                Some(&PING)
+            }
+        }
+
+        let cause = Cause::new(InternalError).src(Ping(0));
+        assert_eq!(cause.chain().count(), MAX_CHAIN_DEPTH);
+        assert_eq!(cause.depth(), MAX_CHAIN_DEPTH - 1);
+        assert_eq!(cause.root_cause().to_string(), "ping 0");
+        assert!(cause.find::<std::fmt::Error>().is_none());
+        assert_eq!(format!("{:#}", cause).lines().count(), MAX_CHAIN_DEPTH + 1);
+    }
+
+    #[test]
+    fn find_works() {
+        use ErrorType::*;
+        use AlphabetError::*;
+        use super::Cause;
+        use std::io;
+
+        let io_err = io::Error::new(io::ErrorKind::NotFound, "no such file");
+        let cause = Cause::new(InternalError).src(Cause::new(AError).src(io_err));
+
+        let found = cause.find::<io::Error>().unwrap();
+        assert_eq!(found.kind(), io::ErrorKind::NotFound);
+        assert!(cause.find::<Cause<AlphabetError>>().is_some());
+        assert!(cause.find::<Cause<ErrorType>>().is_none());
+        assert!(Cause::new(InternalError).find::<io::Error>().is_none());
+    }
+
+    #[test]
+    fn map_keeps_source() {
+        use AlphabetError::*;
+        use super::Cause;
+        use std::error::Error;
+
+        let cause = Cause::new(AError).msg("translated").src(std::io::Error::other("oh no!"));
+        let mapped = cause.map(|e| match e {
+            AError | BError => ErrorType::InvalidArgumentsError,
+            CError => ErrorType::InternalError,
+        });
+        assert_eq!(*mapped, ErrorType::InvalidArgumentsError);
+        assert_eq!(mapped.message(), Some("translated"));
+        assert_eq!(mapped.source().unwrap().to_string(), "oh no!");
+    }
+
+    #[test]
+    fn backtrace_is_opt_in() {
+        use ErrorType::*;
+        use super::Cause;
+
+        assert!(Cause::new(InternalError).backtrace().is_none());
+        assert!(Cause::new(InternalError).with_backtrace().backtrace().is_some());
+        assert!(Cause::new(InternalError).with_backtrace().clone().backtrace().is_none());
+    }
+
+    #[test]
+    fn clone_shares_shared_source() {
+        use ErrorType::*;
+        use super::Cause;
+        use std::error::Error;
+        use std::io;
+        use std::sync::Arc;
+
+        let io_err: Arc<dyn Error + Send + Sync> = Arc::new(io::Error::new(io::ErrorKind::NotFound, "oh no!"));
+        let cause = Cause::new(InternalError).src_shared(io_err.clone());
+        let cloned = cause.clone();
+        assert_eq!(cloned.to_string(), cause.to_string());
+        assert_eq!(cloned.find::<io::Error>().unwrap().kind(), io::ErrorKind::NotFound);
+        assert!(std::ptr::addr_eq(cloned.source().unwrap(), io_err.as_ref()));
+    }
+
+    #[test]
+    fn clone_clones_cloneable_source() {
+        use ErrorType::*;
+        use super::{Cause, StringError};
+        use std::error::Error;
+
+        let cause = Cause::new(InternalError).src_cloneable(StringError::new("oh no!"));
+        let cloned = cause.clone();
+        assert_eq!(cloned, cause);
+        assert_eq!(cloned.find::<StringError>(), Some(&StringError::new("oh no!")));
+        assert!(!std::ptr::addr_eq(cloned.source().unwrap(), cause.source().unwrap()));
+        assert_eq!(format!("{:?}", cloned.source().unwrap()), r#"StringError("oh no!")"#);
+
+        let mut cause = Cause::new(InternalError).src_cloneable(std::fmt::Error);
+        assert!(cause.take_source().unwrap().is::<std::fmt::Error>());
+        let cause = Cause::new(InternalError).src_cloneable(std::fmt::Error);
+        assert!(cause.into_source().unwrap().is::<std::fmt::Error>());
+    }
+
+    #[test]
+    fn src_sync_makes_it_sync() {
+        use ErrorType::*;
+        use super::{Cause, SyncCause};
+        use std::error::Error;
+        use std::sync::{Arc, Mutex};
+
+        fn assert_sync<T: Sync>(_: &T) {}
+
+        let cause: SyncCause<ErrorType> = Cause::new(InternalError)
+            .msg("shared")
+            .src_sync(std::io::Error::other("oh no!"));
+        assert_sync(&cause);
+
+        let shared = Arc::new(Mutex::new(Some(cause)));
+        let cause = shared.lock().unwrap().take().unwrap();
+        assert_eq!(cause.message(), Some("shared"));
+        assert_eq!(cause.source().unwrap().to_string(), "oh no!");
+    }
+
+    #[test]
+    fn source_label_is_configurable() {
+        use ErrorType::*;
+        use super::Cause;
+
+        let cause = Cause::new(InternalError)
+            .src(Cause::new(UnknownError))
+            .with_source_label("Verursacht durch:");
+        assert_eq!(cause.to_string(), "InternalError\n\nVerursacht durch:\n    UnknownError\n");
+        assert_eq!(cause.clone().to_string(), cause.to_string());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn to_json_string_escapes() {
+        use ErrorType::*;
+        use super::Cause;
+
+        let cause = Cause::new(InternalError)
+            .msg("say \"hi\"\n")
+            .src(Cause::new(UnknownError).msg("nested").src(std::io::Error::other("a\\b")));
+        assert_eq!(
+            cause.to_json_string(),
+            concat!(
+                r#"{"cause":"InternalError","message":"say \"hi\"\n","location":null,"#,
+                r#""source":{"error":"UnknownError: nested","source":{"error":"a\\b","source":null}}}"#,
+            )
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn to_json_string_includes_fields() {
+        use ErrorType::*;
+        use super::Cause;
+
+        let cause = Cause::new(InternalError).field("id", "a\"b").field("retries", "3");
+        assert_eq!(
+            cause.to_json_string(),
+            concat!(
+                r#"{"cause":"InternalError","message":null,"location":null,"#,
+                r#""fields":{"id":"a\"b","retries":"3"},"source":null}"#,
+            )
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn to_problem_json_lists_sources() {
+        use ErrorType::*;
+        use super::Cause;
+
+        let cause = Cause::new(InternalError)
+            .msg("line\nbreak")
+            .src(Cause::new(UnknownError).msg("nested").src(std::io::Error::other("root")));
+        assert_eq!(
+            cause.to_problem_json(Some(500)),
+            r#"{"title":"InternalError","status":500,"detail":"line\nbreak","sources":["UnknownError: nested","root"]}"#
+        );
+    }
+
+    #[test]
+    fn msg_with_is_lazy() {
+        use ErrorType::*;
+        use super::Cause;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let cause = Cause::new(InternalError).msg_with(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            "expensive".to_string()
+        });
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+        assert_eq!(cause.to_string(), "InternalError: expensive");
+        assert_eq!(cause.message(), Some("expensive"));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(cause.clone().message(), Some("expensive"));
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        drop(Cause::new(InternalError).msg_with(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            "expensive".to_string()
+        }));
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn notes_are_rendered() {
+        use super::{Cause, StringError};
+
+        let cause = Cause::new(ErrorType::InternalError)
+            .note("first")
+            .note("second")
+            .src(StringError::new("inner"));
+        assert_eq!(
+            format!("{}", cause),
+            "InternalError\n\nNotes:\n    - first\n    - second\n\nCaused by:\n    inner\n"
+        );
+        assert_eq!(
+            format!("{:#}", cause),
+            "0: InternalError\n1: inner\n\nNotes:\n    - first\n    - second"
+        );
+        assert_eq!(format!("{:-}", cause), "InternalError");
+        assert!(Cause::new(ErrorType::InternalError).notes().is_empty());
+    }
+
+    #[test]
+    fn help_is_rendered_after_notes() {
+        use super::{Cause, StringError};
+
+        let cause = Cause::new(ErrorType::InternalError)
+            .help("try again")
+            .note("a note")
+            .src(StringError::new("inner"));
+        assert_eq!(
+            format!("{}", cause),
+            "InternalError\n\nNotes:\n    - a note\n\nhelp: try again\n\nCaused by:\n    inner\n"
+        );
+        assert_eq!(format!("{:-}", cause), "InternalError");
+        assert_eq!(Cause::new(ErrorType::InternalError).help_text(), None);
+    }
+
+    #[test]
+    fn clear_works() {
+        use super::Cause;
+
+        let mut cause = Cause::new(ErrorType::InternalError).msg("oops").src(std::fmt::Error);
+        assert!(cause.has_source());
+        assert_eq!(cause.clear_source().unwrap().to_string(), std::fmt::Error.to_string());
+        assert!(!cause.has_source());
+        assert!(cause.clear_source().is_none());
+        cause.clear_message();
+        assert_eq!(cause.message(), None);
+        assert_eq!(cause.to_string(), "InternalError");
+    }
+
+    #[test]
+    fn aggregate_works() {
+        use super::{Cause, StringError};
+        use std::error::Error;
+
+        let errors: Vec<Box<dyn Error + Send>> = vec![
+            Box::new(Cause::new(ErrorType::InvalidArgumentsError).msg("bad name").src(StringError::new("hidden"))),
+            Box::new(std::fmt::Error),
+        ];
+        let cause = Cause::aggregate(ErrorType::InternalError, errors).src(StringError::new("inner"));
+        assert_eq!(
+            cause.to_string(),
+            concat!(
+                "InternalError\n\nErrors:\n    0: InvalidArgumentsError: bad name\n",
+                "    1: an error occurred when formatting an argument\n\nCaused by:\n    inner\n",
+            )
+        );
+        assert!(cause.sub_errors()[0].is::<Cause<ErrorType>>());
+
+        let cloned = cause.clone();
+        assert_eq!(cloned.sub_errors()[1].to_string(), "an error occurred when formatting an argument");
+        assert!(cloned.sub_errors()[1].is::<StringError>());
+
+        let sync = cause.src_sync(StringError::new("inner"));
+        assert!(sync.sub_errors()[0].is::<StringError>());
+        assert!(Cause::new(ErrorType::InternalError).sub_errors().is_empty());
+    }
+
+    #[test]
+    fn report_verbose_renders_timestamp() {
+        use super::Cause;
+
+        let mut buf = Vec::new();
+        Cause::new(ErrorType::InternalError).with_timestamp().report_verbose(&mut buf).unwrap();
+        let report = String::from_utf8(buf).unwrap();
+        assert!(report.starts_with("InternalError\n\nTimestamp:\n    "), "{}", report);
+        assert!(Cause::new(ErrorType::InternalError).timestamp().is_none());
+    }
+
+    #[test]
+    fn flatten_merges_details() {
+        use ErrorType::*;
+        use super::{Cause, StringError};
+
+        let cause = Cause::new(InternalError)
+            .note("outer")
+            .src(
+                Cause::new(InternalError)
+                    .msg("inner")
+                    .note("inner")
+                    .help("retry")
+                    .src(Cause::new(UnknownError).msg("root").src(StringError::new("io"))),
+            )
+            .flatten();
+        assert_eq!(cause.message(), Some("inner"));
+        assert_eq!(cause.notes(), ["outer", "inner"]);
+        assert_eq!(cause.help_text(), Some("retry"));
+        assert_eq!(cause.depth(), 2);
+        assert_eq!(cause.find_cause::<ErrorType>(), Some(&UnknownError));
+
+        let cause = Cause::new(InternalError).src(StringError::new("io")).flatten();
+        assert_eq!(cause.root_cause().to_string(), "io");
+    }
+
+    #[test]
+    fn context_is_popped_by_guard() {
+        use super::{push_context, Cause};
+
+        let outer = push_context("request_id", "abc");
+        let inner = push_context("user", "alice");
+        assert_eq!(Cause::new(ErrorType::InternalError).fields().len(), 2);
+        drop(inner);
+        assert_eq!(Cause::new(ErrorType::InternalError).fields(), [("request_id", "abc".to_string())]);
+
+        let _inner = push_context("user", "bob");
+        drop(outer);
+        assert!(Cause::new(ErrorType::InternalError).fields().is_empty());
+        std::thread::spawn(|| assert!(Cause::new(ErrorType::InternalError).fields().is_empty())).join().unwrap();
+    }
+
+    #[test]
+    fn merge_keeps_both() {
+        use super::Cause;
+
+        let cause = Cause::new(ErrorType::InternalError)
+            .note("outer")
+            .merge(Cause::new(ErrorType::UnknownError).msg("cleanup").note("inner"));
+        assert_eq!(*cause, ErrorType::InternalError);
+        assert_eq!(cause.message(), Some("cleanup"));
+        assert_eq!(cause.notes(), ["outer", "inner"]);
+        assert_eq!(cause.sub_errors().len(), 1);
+        assert!(cause.sub_errors()[0].is::<Cause<ErrorType>>());
+    }
+
+    #[test]
+    fn debug_is_pretty_printable() {
+        use super::{Cause, StringError};
+
+        let cause = Cause::new(ErrorType::InternalError)
+            .note("retried")
+            .src(Cause::new(ErrorType::UnknownError).msg("inner").src(StringError::new("oh no!")));
+        let debug = format!("{:#?}", cause);
+        assert!(debug.starts_with("Cause {\n    cause: InternalError,\n"), "{}", debug);
+        assert!(debug.contains("notes: [\n        \"retried\",\n    ],\n"), "{}", debug);
+        assert!(debug.ends_with("sources: [\n        \"UnknownError: inner\",\n        \"oh no!\",\n    ],\n}"), "{}", debug);
+    }
+
+    #[test]
+    fn source_as_checks_type() {
+        use super::{Cause, StringError};
+
+        let cause = Cause::new(ErrorType::InternalError).src(Cause::new(AlphabetError::AError));
+        assert_eq!(cause.source_as::<AlphabetError>().map(Cause::cause), Some(&AlphabetError::AError));
+        assert!(cause.source_as::<ErrorType>().is_none());
+
+        let cause = Cause::new(ErrorType::InternalError).src(Cause::new(ErrorType::UnknownError).src(Cause::new(AlphabetError::AError)));
+        assert!(cause.source_as::<AlphabetError>().is_none());
+        assert!(Cause::new(ErrorType::InternalError).src(StringError::new("io")).source_as::<ErrorType>().is_none());
+        assert!(Cause::new(ErrorType::InternalError).source_as::<ErrorType>().is_none());
+    }
+
+    #[test]
+    fn source_send_unwraps_shared_source() {
+        use super::Cause;
+        use std::error::Error;
+        use std::sync::Arc;
+
+        let fmt_err: Arc<dyn Error + Send + Sync> = Arc::new(std::fmt::Error);
+        let cause = Cause::new(ErrorType::InternalError).src_shared(fmt_err.clone());
+        assert!(std::ptr::addr_eq(cause.source_send().unwrap(), fmt_err.as_ref()));
+
+        let cause = Cause::new(ErrorType::InternalError).src_cloneable(std::fmt::Error);
+        assert!(cause.source_send().unwrap().is::<std::fmt::Error>());
+        assert!(Cause::new(ErrorType::InternalError).source_send().is_none());
+    }
+
+    #[test]
+    fn into_io_error_keeps_sync_source() {
+        use super::{Cause, LocalCause, StringError, SyncCause};
+
+        let io_err = std::io::Error::from(Cause::new(ErrorType::InternalError).src_sync(std::fmt::Error));
+        let inner = io_err.get_ref().unwrap().downcast_ref::<SyncCause<ErrorType>>().unwrap();
+        assert!(inner.find::<std::fmt::Error>().is_some());
+
+        let io_err = std::io::Error::from(Cause::new(ErrorType::InternalError).src(std::fmt::Error).note("kept"));
+        let inner = io_err.get_ref().unwrap().downcast_ref::<SyncCause<ErrorType>>().unwrap();
+        assert!(inner.find::<StringError>().is_some());
+        assert_eq!(inner.notes(), ["kept"]);
+
+        let local: LocalCause<ErrorType> = Cause::new(ErrorType::InternalError).src_local(std::fmt::Error);
+        assert_eq!(std::io::Error::from(local).to_string(), "InternalError\n\nCaused by:\n    an error occurred when formatting an argument\n");
+    }
+
+    #[test]
+    fn sign_plus_drops_nested_trailing_newlines() {
+        use super::Cause;
+
+        let cause = Cause::new(ErrorType::InternalError).src(Cause::new(ErrorType::UnknownError).src(std::fmt::Error));
+        assert_eq!(
+            format!("{:+}", cause),
+            "InternalError\n\nCaused by:\n    UnknownError\n\nCaused by:\n    an error occurred when formatting an argument"
+        );
+        assert!(format!("{}", cause).ends_with("argument\n\n"));
+    }
+
+    #[test]
+    fn strip_path_prefix_respects_components() {
+        use super::strip_path_prefix;
+        assert_eq!(strip_path_prefix("src/x.rs", "sr"), "src/x.rs");
+        assert_eq!(strip_path_prefix("src/x.rs", "src"), "x.rs");
+        assert_eq!(strip_path_prefix("src/x.rs", "src/"), "x.rs");
+        assert_eq!(strip_path_prefix("src\\x.rs", "src"), "x.rs");
+        assert_eq!(strip_path_prefix("src/x.rs", ""), "src/x.rs");
+    }
+
+    #[test]
+    fn src_boxed_keeps_the_box() {
+        use super::Cause;
+        use std::error::Error;
+
+        let boxed: Box<dyn Error + Send> = Box::new(std::io::Error::other("disk full"));
+        let addr = boxed.as_ref() as *const (dyn Error + Send);
+        let cause = Cause::new(ErrorType::InternalError).src_boxed(boxed);
+        assert!(cause.source().unwrap().is::<std::io::Error>());
+        assert!(std::ptr::addr_eq(cause.source().unwrap(), addr));
+    }
+
+    #[test]
+    fn take_and_into_source_move_it_out() {
+        use super::Cause;
+        use std::error::Error;
+
+        let mut cause = Cause::new(ErrorType::InternalError).src(std::fmt::Error);
+        assert!(cause.take_source().unwrap().is::<std::fmt::Error>());
+        assert!(cause.source().is_none());
+        assert!(cause.take_source().is_none());
+
+        let cause = Cause::new(ErrorType::InternalError).src(std::fmt::Error);
+        assert!(cause.into_source().unwrap().is::<std::fmt::Error>());
+        assert!(Cause::new(ErrorType::InternalError).into_source().is_none());
+    }
+
+    #[test]
+    fn set_cause_replaces_only_the_cause() {
+        use super::Cause;
+
+        let mut cause = Cause::new(ErrorType::InternalError).msg("kept").src(std::fmt::Error);
+        cause.set_cause(ErrorType::UnknownError);
+        assert_eq!(*cause.cause(), ErrorType::UnknownError);
+        assert_eq!(cause.message(), Some("kept"));
+        assert!(cause.find::<std::fmt::Error>().is_some());
+    }
+
+    #[test]
+    fn taken_shared_source_is_unwrapped() {
+        use super::Cause;
+        use std::error::Error;
+        use std::sync::Arc;
+
+        let shared: Arc<dyn Error + Send + Sync> = Arc::new(std::fmt::Error);
+        let mut cause = Cause::new(ErrorType::InternalError).src_shared(shared.clone());
+        let src = cause.clear_source().unwrap();
+        assert!(Arc::ptr_eq(src.downcast_ref::<Arc<dyn Error + Send + Sync>>().unwrap(), &shared));
+
+        let cause = Cause::new(ErrorType::InternalError).src_shared(shared.clone());
+        let src = cause.into_source().unwrap();
+        assert!(src.downcast_ref::<Arc<dyn Error + Send + Sync>>().unwrap().is::<std::fmt::Error>());
+    }
+
+    #[test]
+    fn result_ext_works() {
+        use ErrorType::*;
+        use super::ResultExt;
+        use std::error::Error;
+        use std::io;
+
+        let ok: Result<u32, io::Error> = Ok(1);
+        assert_eq!(ok.cause(InternalError).unwrap(), 1);
+
+        let err: Result<u32, io::Error> = Err(io::Error::other("oh no!"));
+        let cause = err.cause_msg(InternalError, "failed").unwrap_err();
+        assert_eq!(*cause, InternalError);
+        assert_eq!(cause.message(), Some("failed"));
+        assert_eq!(cause.source().unwrap().to_string(), "oh no!");
+
+        let err: Result<u32, io::Error> = Err(io::Error::other("oh no!"));
+        let cause = err
+            .map_err_cause_with(|e| if e.kind() == io::ErrorKind::Other { UnknownError } else { InternalError })
+            .unwrap_err();
+        assert_eq!(*cause, UnknownError);
+        assert_eq!(cause.source().unwrap().to_string(), "oh no!");
+    }
 }
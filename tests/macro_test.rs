@@ -9,10 +9,10 @@ enum ErrorType {
 #[test]
 fn one_argument_macro_test() {
     let cause = cause!(ErrorType::SomeError);
-    if cfg!(debug_assertions) { // $ cargo test
+    if cfg!(all(any(debug_assertions, feature = "release-location"), not(feature = "no-location"))) { // $ cargo test
         assert_eq!(
             format!("{}", cause),
-            "SomeError: [tests/macro_test.rs:11]".to_string()
+            "SomeError: [tests/macro_test.rs:11:17]".to_string()
         );
     } else {                    // $ cargo test --release
         assert_eq!(
@@ -25,10 +25,10 @@ fn one_argument_macro_test() {
 #[test]
 fn two_argument_macro_test() {
     let cause = cause!(ErrorType::AnotherError, "Something went wrong!");
-    if cfg!(debug_assertions) { // $ cargo test
+    if cfg!(all(any(debug_assertions, feature = "release-location"), not(feature = "no-location"))) { // $ cargo test
         assert_eq!(
             format!("{}", cause),
-            "AnotherError: Something went wrong! [tests/macro_test.rs:27]".to_string()
+            "AnotherError: Something went wrong! [tests/macro_test.rs:27:17]".to_string()
         );
     } else {                    // $ cargo test --release
         assert_eq!(
@@ -37,3 +37,149 @@ fn two_argument_macro_test() {
         );
     }
 }
+
+#[test]
+fn location_test() {
+    let cause = cause!(ErrorType::SomeError, "Something went wrong!");
+    assert_eq!(cause.message(), Some("Something went wrong!"));
+    if cfg!(all(any(debug_assertions, feature = "release-location"), not(feature = "no-location"))) {
+        let location = cause.location().unwrap();
+        assert_eq!(location.file(), "tests/macro_test.rs");
+        assert_eq!(location.line(), 43);
+        assert_eq!(location.column(), 17);
+        assert_eq!(cause.module(), Some("macro_test"));
+    } else {
+        assert!(cause.location().is_none());
+        assert!(cause.module().is_none());
+    }
+}
+
+#[test]
+fn three_argument_macro_test() {
+    use std::error::Error;
+    let io_err = std::io::Error::other("oh no!");
+    let cause = cause!(ErrorType::AnotherError, "Failed to read."; io_err);
+    assert!(cause.source().is_some());
+    if cfg!(all(any(debug_assertions, feature = "release-location"), not(feature = "no-location"))) {
+        assert_eq!(
+            format!("{}", cause),
+            "AnotherError: Failed to read. [tests/macro_test.rs:61:17]\n\nCaused by:\n    oh no!\n".to_string()
+        );
+    } else {
+        assert_eq!(
+            format!("{}", cause),
+            "AnotherError: Failed to read.\n\nCaused by:\n    oh no!\n".to_string()
+        );
+    }
+}
+
+#[test]
+fn format_arguments_macro_test() {
+    use std::error::Error;
+    let (id, table) = (42, "users");
+    let cause = cause!(ErrorType::SomeError, "user {} not in {}", id, table);
+    assert!(cause.source().is_none());
+    let io_err = std::io::Error::other("oh no!");
+    let with_source = cause!(ErrorType::SomeError, "user {} not in {}", id, table; io_err);
+    assert!(with_source.source().is_some());
+    if cfg!(all(any(debug_assertions, feature = "release-location"), not(feature = "no-location"))) {
+        assert_eq!(
+            format!("{}", cause),
+            "SomeError: user 42 not in users [tests/macro_test.rs:80:17]".to_string()
+        );
+    } else {
+        assert_eq!(
+            format!("{}", cause),
+            "SomeError: user 42 not in users".to_string()
+        );
+    }
+}
+
+fn bail_with(id: u32) -> Result<(), cause::Cause<ErrorType>> {
+    if id == 0 {
+        cause::bail!(ErrorType::SomeError);
+    }
+    if id == 1 {
+        cause::bail!(ErrorType::SomeError, "invalid id {}", id);
+    }
+    Ok(())
+}
+
+#[test]
+fn bail_macro_test() {
+    assert!(bail_with(2).is_ok());
+    let one_argument = bail_with(0).unwrap_err();
+    let format_arguments = bail_with(1).unwrap_err();
+    if cfg!(all(any(debug_assertions, feature = "release-location"), not(feature = "no-location"))) {
+        assert_eq!(
+            format!("{}", one_argument),
+            "SomeError: [tests/macro_test.rs:100:9]".to_string()
+        );
+        assert_eq!(
+            format!("{}", format_arguments),
+            "SomeError: invalid id 1 [tests/macro_test.rs:103:9]".to_string()
+        );
+    } else {
+        assert_eq!(format!("{}", one_argument), "SomeError".to_string());
+        assert_eq!(format!("{}", format_arguments), "SomeError: invalid id 1".to_string());
+    }
+}
+
+fn ensure_with(id: u32) -> Result<(), cause::Cause<ErrorType>> {
+    cause::ensure!(id != 0, ErrorType::AnotherError);
+    cause::ensure!(id != 1, ErrorType::AnotherError, "invalid id {}", id);
+    Ok(())
+}
+
+#[test]
+fn ensure_macro_test() {
+    assert!(ensure_with(2).is_ok());
+    let one_argument = ensure_with(0).unwrap_err();
+    let format_arguments = ensure_with(1).unwrap_err();
+    if cfg!(all(any(debug_assertions, feature = "release-location"), not(feature = "no-location"))) {
+        assert_eq!(
+            format!("{}", one_argument),
+            "AnotherError: [tests/macro_test.rs:129:5]".to_string()
+        );
+        assert_eq!(
+            format!("{}", format_arguments),
+            "AnotherError: invalid id 1 [tests/macro_test.rs:130:5]".to_string()
+        );
+    } else {
+        assert_eq!(format!("{}", one_argument), "AnotherError".to_string());
+        assert_eq!(format!("{}", format_arguments), "AnotherError: invalid id 1".to_string());
+    }
+}
+
+#[test]
+fn new_tracks_caller_test() {
+    let cause = cause::Cause::new(ErrorType::SomeError).msg("oops");
+    assert_eq!(format!("{}", cause), "SomeError: oops".to_string());
+    assert_eq!(cause.module(), None);
+    if cfg!(all(any(debug_assertions, feature = "release-location"), not(feature = "no-location"))) {
+        let location = cause.location().unwrap();
+        assert_eq!(location.file(), "tests/macro_test.rs");
+        assert_eq!(location.line(), 156);
+        assert_eq!(location.column(), 17);
+    } else {
+        assert!(cause.location().is_none());
+    }
+}
+
+#[test]
+fn inline_format_arguments_test() {
+    let id = 42;
+    let inline = cause!(ErrorType::SomeError, "id {id}");
+    assert_eq!(inline.message(), Some("id 42"));
+
+    let io_err = std::io::Error::other("oh no!");
+    let inline = cause!(ErrorType::SomeError, "id {id}"; io_err);
+    assert_eq!(inline.message(), Some("id 42"));
+
+    let named = cause!(ErrorType::SomeError, "{n} of {total}", n = 1, total = id; std::fmt::Error);
+    assert_eq!(named.message(), Some("1 of 42"));
+    assert!(named.root_cause().is::<std::fmt::Error>());
+
+    let named = cause!(ErrorType::SomeError, "{n}", n = 1);
+    assert_eq!(named.message(), Some("1"));
+}